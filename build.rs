@@ -3,76 +3,186 @@ use std::fs;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+use sha2::{Digest, Sha256};
+
+/// Directory containing `libchdb.so`/`libchdb.dylib`; skips discovery and download.
+const ENV_LIB_DIR: &str = "CHDB_LIB_DIR";
+/// Directory containing `chdb.h`; defaults to a location next to the library.
+const ENV_INCLUDE_DIR: &str = "CHDB_INCLUDE_DIR";
+/// When set to `1`, fail instead of downloading libchdb.
+const ENV_OFFLINE: &str = "CHDB_OFFLINE";
+/// Expected SHA-256 of the downloaded archive, overriding the pinned digest.
+const ENV_LIB_SHA256: &str = "CHDB_LIB_SHA256";
+
+const LIBCHDB_VERSION: &str = "v26.1.0";
+
+/// SHA-256 digests of the `LIBCHDB_VERSION` release archives, by asset name.
+///
+/// Keep in sync with `update_libchdb.sh`. When bumping the version, fill in
+/// the output of `sha256sum *-libchdb.tar.gz` over the release assets.
+/// Assets without an entry are downloaded unverified, with a build warning,
+/// unless `CHDB_LIB_SHA256` is set.
+// TODO: fill in the digests of the v26.1.0 assets.
+const LIBCHDB_SHA256: &[(&str, &str)] = &[];
+
+const LIB_NAMES: &[&str] = &["libchdb.so", "libchdb.dylib"];
+
+type BuildResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+/// Where libchdb was found. The header is only needed to run bindgen.
+struct Libchdb {
+    lib_dir: PathBuf,
+    header_path: Option<PathBuf>,
+}
+
 fn main() {
-    if env::var("DOCS_RS").is_ok() {
+    println!("cargo:rerun-if-env-changed={ENV_LIB_DIR}");
+    println!("cargo:rerun-if-env-changed={ENV_INCLUDE_DIR}");
+    println!("cargo:rerun-if-env-changed={ENV_OFFLINE}");
+    println!("cargo:rerun-if-env-changed={ENV_LIB_SHA256}");
+    println!("cargo:rustc-check-cfg=cfg(chdb_generated_bindings)");
+
+    // With `dynamic-loading`, libchdb is opened at runtime and not linked.
+    if env::var("DOCS_RS").is_ok() || env::var_os("CARGO_FEATURE_DYNAMIC_LOADING").is_some() {
         return;
     }
 
     let out_dir = env::var("OUT_DIR").unwrap();
     let out_path = PathBuf::from(&out_dir);
-    let libchdb_info = find_libchdb_or_download(&out_path);
-    match libchdb_info {
-        Ok((lib_dir, header_path)) => {
-            setup_link_paths(&lib_dir);
-            generate_bindings(&header_path, &out_path);
-        }
-        Err(e) => {
-            eprintln!("Failed to find or download libchdb: {e}");
-            println!("cargo:warning=Failed to find libchdb. Please install manually using './update_libchdb.sh --local' or '--global'");
-            std::process::exit(1);
-        }
+    if let Err(e) = link_and_generate_bindings(&out_path) {
+        eprintln!("Failed to find or download libchdb: {e}");
+        println!("cargo:warning=Failed to find libchdb: {e}");
+        println!("cargo:warning=Set CHDB_LIB_DIR, or install manually using './update_libchdb.sh --local' or '--global'");
+        std::process::exit(1);
     }
 }
 
-fn find_libchdb_or_download(
-    out_dir: &Path,
-) -> Result<(PathBuf, PathBuf), Box<dyn std::error::Error>> {
-    if let Some((lib_dir, header_path)) = find_existing_libchdb() {
-        return Ok((lib_dir, header_path));
+fn link_and_generate_bindings(out_dir: &Path) -> BuildResult<()> {
+    let libchdb = find_libchdb_or_download(out_dir)?;
+    setup_link_paths(&libchdb.lib_dir);
+
+    // With `vendored-bindings`, the checked-in src/bindings.rs is used as-is.
+    if env::var_os("CARGO_FEATURE_VENDORED_BINDINGS").is_some() {
+        return Ok(());
+    }
+    let header_path = libchdb.header_path.ok_or(
+        "chdb.h not found. Set CHDB_INCLUDE_DIR or enable the `vendored-bindings` feature",
+    )?;
+    generate_bindings(&header_path, out_dir);
+    // Use OUT_DIR/bindings.rs instead of the checked-in fallback.
+    println!("cargo:rustc-cfg=chdb_generated_bindings");
+    Ok(())
+}
+
+fn find_libchdb_or_download(out_dir: &Path) -> BuildResult<Libchdb> {
+    if let Some(lib_dir) = env::var_os(ENV_LIB_DIR) {
+        let lib_dir = PathBuf::from(lib_dir);
+        if !has_library(&lib_dir) {
+            return Err(format!(
+                "{ENV_LIB_DIR} is set but {} has no libchdb",
+                lib_dir.display()
+            )
+            .into());
+        }
+        let header_path = find_header(&[lib_dir.clone(), lib_dir.join("../include")]);
+        return Ok(Libchdb {
+            lib_dir,
+            header_path,
+        });
+    }
+
+    if let Some(lib) = find_with_pkg_config() {
+        return Ok(lib);
+    }
+
+    if let Some(lib) = find_existing_libchdb() {
+        return Ok(lib);
+    }
+
+    if env::var(ENV_OFFLINE).is_ok_and(|v| v == "1") {
+        return Err(format!(
+            "libchdb not found and {ENV_OFFLINE}=1 forbids downloading it. Set {ENV_LIB_DIR} to its location"
+        )
+        .into());
     }
 
     println!("cargo:warning=libchdb not found locally, attempting to download...");
     download_libchdb_to_out_dir(out_dir)?;
-    let lib_dir = out_dir.to_path_buf();
     let header_path = out_dir.join("chdb.h");
 
     if !header_path.exists() {
         return Err("Header file not found after download".into());
     }
 
-    Ok((lib_dir, header_path))
+    Ok(Libchdb {
+        lib_dir: out_dir.to_path_buf(),
+        header_path: Some(header_path),
+    })
+}
+
+fn has_library(dir: &Path) -> bool {
+    LIB_NAMES.iter().any(|name| dir.join(name).exists())
 }
 
-fn find_existing_libchdb() -> Option<(PathBuf, PathBuf)> {
-    if Path::new("./libchdb.so").exists() && Path::new("./chdb.h").exists() {
-        return Some((PathBuf::from("."), PathBuf::from("./chdb.h")));
+/// Find `chdb.h`, preferring `CHDB_INCLUDE_DIR` over the given candidate directories.
+fn find_header(candidates: &[PathBuf]) -> Option<PathBuf> {
+    if let Some(include_dir) = env::var_os(ENV_INCLUDE_DIR) {
+        let header = PathBuf::from(include_dir).join("chdb.h");
+        return header.exists().then_some(header);
+    }
+    candidates
+        .iter()
+        .map(|dir| dir.join("chdb.h"))
+        .find(|header| header.exists())
+}
+
+/// Look up `chdb.pc` with pkg-config, honoring `PKG_CONFIG_PATH`.
+fn find_with_pkg_config() -> Option<Libchdb> {
+    let library = pkg_config::Config::new()
+        .cargo_metadata(false)
+        .env_metadata(true)
+        .probe("chdb")
+        .ok()?;
+    let lib_dir = library
+        .link_paths
+        .into_iter()
+        .find(|dir| has_library(dir))?;
+    let header_path = find_header(&library.include_paths);
+    Some(Libchdb {
+        lib_dir,
+        header_path,
+    })
+}
+
+fn find_existing_libchdb() -> Option<Libchdb> {
+    if has_library(Path::new(".")) {
+        return Some(Libchdb {
+            lib_dir: PathBuf::from("."),
+            header_path: find_header(&[PathBuf::from(".")]),
+        });
     }
 
     // Check system installation
     let system_lib_path = Path::new("/usr/local/lib");
-    let system_header_path = Path::new("/usr/local/include/chdb.h");
-
-    if system_header_path.exists()
-        && (system_lib_path.join("libchdb.so").exists()
-            || system_lib_path.join("libchdb.dylib").exists())
-    {
-        return Some((
-            system_lib_path.to_path_buf(),
-            system_header_path.to_path_buf(),
-        ));
+    if has_library(system_lib_path) {
+        return Some(Libchdb {
+            lib_dir: system_lib_path.to_path_buf(),
+            header_path: find_header(&[PathBuf::from("/usr/local/include")]),
+        });
     }
 
     None
 }
 
-fn download_libchdb_to_out_dir(out_dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+fn download_libchdb_to_out_dir(out_dir: &Path) -> BuildResult<()> {
     let platform = get_platform_string()?;
-    let version = "v26.1.0";
-    let url =
-        format!("https://github.com/chdb-io/chdb-core/releases/download/{version}/{platform}");
+    let url = format!(
+        "https://github.com/chdb-io/chdb-core/releases/download/{LIBCHDB_VERSION}/{platform}"
+    );
     println!("cargo:warning=Downloading libchdb from: {url}");
-    let response = reqwest::blocking::get(&url)?;
+    let response = reqwest::blocking::get(&url)?.error_for_status()?;
     let content = response.bytes()?;
+    verify_sha256(&platform, &content)?;
     let temp_archive = out_dir.join("libchdb.tar.gz");
     fs::write(&temp_archive, content)?;
     let file = fs::File::open(&temp_archive)?;
@@ -91,6 +201,40 @@ fn download_libchdb_to_out_dir(out_dir: &Path) -> Result<(), Box<dyn std::error:
     Ok(())
 }
 
+/// Check the archive against `CHDB_LIB_SHA256` or the pinned digest before unpacking it.
+fn verify_sha256(platform: &str, content: &[u8]) -> BuildResult<()> {
+    let Some(expected) = expected_sha256(platform) else {
+        println!(
+            "cargo:warning=No pinned SHA-256 for {platform} {LIBCHDB_VERSION}; \
+             set {ENV_LIB_SHA256} to verify the download"
+        );
+        return Ok(());
+    };
+    let actual: String = Sha256::digest(content)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    if actual != expected {
+        return Err(format!(
+            "SHA-256 mismatch for {platform} {LIBCHDB_VERSION}: expected {expected}, got {actual}. \
+             The download may be corrupted or tampered with; set {ENV_LIB_SHA256} for custom builds"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// The digest from `CHDB_LIB_SHA256`, or the pinned one for `platform`.
+fn expected_sha256(platform: &str) -> Option<String> {
+    match env::var(ENV_LIB_SHA256) {
+        Ok(digest) => Some(digest.trim().to_ascii_lowercase()),
+        Err(_) => LIBCHDB_SHA256
+            .iter()
+            .find(|(asset, _)| *asset == platform)
+            .map(|(_, digest)| digest.to_string()),
+    }
+}
+
 fn get_platform_string() -> Result<String, &'static str> {
     let os = env::consts::OS;
     let arch = env::consts::ARCH;
@@ -104,6 +248,18 @@ fn get_platform_string() -> Result<String, &'static str> {
 }
 
 fn setup_link_paths(lib_dir: &Path) {
+    let lib_dir = lib_dir
+        .canonicalize()
+        .unwrap_or_else(|_| lib_dir.to_path_buf());
+    // Exposed to dependents' build scripts as DEP_CHDB_LIB_DIR, so that they
+    // can emit the rpath for their own binaries: link arguments only ever
+    // apply to the package whose build script emits them.
+    println!("cargo:lib_dir={}", lib_dir.display());
+    if env::var_os("CARGO_FEATURE_RPATH").is_some()
+        && env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os != "windows")
+    {
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_dir.display());
+    }
     println!("cargo:rustc-link-search={}", lib_dir.display());
     println!("cargo:rustc-link-search=./");
     println!("cargo:rustc-link-search=/usr/local/lib");
@@ -126,10 +282,6 @@ fn generate_bindings(header_path: &Path, out_dir: &Path) {
         .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()))
         .generate()
         .expect("Unable to generate bindings");
-    let src_path = PathBuf::from("./src/");
-    bindings
-        .write_to_file(src_path.join("bindings.rs"))
-        .expect("Couldn't write bindings to src!");
     bindings
         .write_to_file(out_dir.join("bindings.rs"))
         .expect("Couldn't write bindings to OUT_DIR!");
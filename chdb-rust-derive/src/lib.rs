@@ -0,0 +1,152 @@
+//! Derive macros for [chdb-rust](https://docs.rs/chdb-rust).
+//!
+//! This crate is not meant to be used directly; enable the `derive` feature of
+//! `chdb-rust` and use `chdb_rust::ChdbRow` instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitStr};
+
+/// Derive `chdb_rust::row::Row` for a struct with named fields.
+///
+/// Each field becomes a column, in declaration order. Field types must
+/// implement `chdb_rust::row::RowBinary`.
+///
+/// # Field attributes
+///
+/// - `#[chdb(rename = "name")]` - use a different column name
+/// - `#[chdb(type = "LowCardinality(String)")]` - override the ClickHouse type
+///   used in the generated DDL and `format()` structure
+/// - `#[chdb(codec = "ZSTD(3)")]` - add a compression codec to the column
+/// - `#[chdb(order_by)]` - include the column in the `ORDER BY` key
+///
+/// # Examples
+///
+/// ```ignore
+/// use chdb_rust::ChdbRow;
+///
+/// #[derive(ChdbRow)]
+/// struct Event {
+///     #[chdb(order_by)]
+///     id: u64,
+///     #[chdb(type = "LowCardinality(String)")]
+///     kind: String,
+/// }
+/// ```
+#[proc_macro_derive(ChdbRow, attributes(chdb))]
+pub fn derive_chdb_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    ty: Option<String>,
+    codec: Option<String>,
+    order_by: bool,
+}
+
+impl FieldAttrs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut out = Self::default();
+        for attr in attrs.iter().filter(|a| a.path().is_ident("chdb")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    out.rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("type") {
+                    out.ty = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("codec") {
+                    out.codec = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("order_by") {
+                    out.order_by = true;
+                } else {
+                    return Err(meta.error("unknown chdb attribute"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(out)
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "ChdbRow can only be derived for structs",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "ChdbRow requires a struct with named fields",
+        ));
+    };
+
+    let mut names = Vec::new();
+    let mut defs = Vec::new();
+    let mut encodes = Vec::new();
+    let mut decodes = Vec::new();
+
+    for field in &fields.named {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+
+        let name = attrs
+            .rename
+            .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+        let ch_type = match attrs.ty {
+            Some(t) => quote! { ::std::string::String::from(#t) },
+            None => quote! { <#ty as ::chdb_rust::row::RowBinary>::clickhouse_type() },
+        };
+        let codec = match attrs.codec {
+            Some(c) => quote! { ::std::option::Option::Some(#c) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let order_by = attrs.order_by;
+
+        defs.push(quote! {
+            ::chdb_rust::row::ColumnDef {
+                name: #name,
+                ty: #ch_type,
+                codec: #codec,
+                order_by: #order_by,
+            }
+        });
+        encodes.push(quote! {
+            ::chdb_rust::row::RowBinary::encode(&self.#ident, buf);
+        });
+        decodes.push(quote! {
+            #ident: <#ty as ::chdb_rust::row::RowBinary>::decode(buf)?,
+        });
+        names.push(name);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::chdb_rust::row::Row for #ident #ty_generics #where_clause {
+            const COLUMNS: &'static [&'static str] = &[#(#names),*];
+
+            fn column_defs() -> ::std::vec::Vec<::chdb_rust::row::ColumnDef> {
+                ::std::vec![#(#defs),*]
+            }
+
+            fn encode_row(&self, buf: &mut ::std::vec::Vec<u8>) {
+                #(#encodes)*
+            }
+
+            fn decode_row(buf: &mut &[u8]) -> ::chdb_rust::error::Result<Self> {
+                ::std::result::Result::Ok(Self {
+                    #(#decodes)*
+                })
+            }
+        }
+    })
+}
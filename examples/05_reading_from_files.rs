@@ -5,6 +5,8 @@ use chdb_rust::arg::Arg;
 /// ClickHouse's `file()` function.
 use chdb_rust::execute;
 use chdb_rust::format::{InputFormat, OutputFormat};
+use chdb_rust::query_builder::Select;
+use chdb_rust::table_function::TableFunction;
 
 fn main() -> Result<(), chdb_rust::error::Error> {
     println!("=== Reading from Files Examples ===\n");
@@ -13,10 +15,8 @@ fn main() -> Result<(), chdb_rust::error::Error> {
     // You can modify the path to point to your own file
 
     println!("1. Reading from CSV file:");
-    let query = format!(
-        "SELECT * FROM file('tests/logs.csv', {})",
-        InputFormat::CSV.as_str()
-    );
+    // The CSV format is inferred from the file extension.
+    let query = Select::from_table_function(&TableFunction::file("tests/logs.csv"))?.to_sql()?;
 
     match execute(
         &query,
@@ -43,10 +43,8 @@ fn main() -> Result<(), chdb_rust::error::Error> {
     println!();
 
     println!("3. Reading from JSON file (example query):");
-    let query = format!(
-        "SELECT * FROM file('data.json', {}) LIMIT 10",
-        InputFormat::JSONEachRow.as_str()
-    );
+    let source = TableFunction::file("data.json").format(InputFormat::JSONEachRow);
+    let query = Select::from_table_function(&source)?.limit(10).to_sql()?;
     println!("Query would be: {query}");
     println!("(Update the path to point to your JSON file)");
 
@@ -7,7 +7,7 @@ use std::borrow::Cow;
 use std::ffi::CString;
 
 use crate::error::Error;
-use crate::format::OutputFormat;
+use crate::format::{FormatOptions, OutputFormat};
 use crate::log_level::LogLevel;
 
 /// Query arguments that can be passed when executing queries.
@@ -42,6 +42,12 @@ pub enum Arg<'a> {
     OutputFormat(OutputFormat),
     /// --multiquery
     MultiQuery,
+    /// Per-query format settings, added to the query's `SETTINGS` clause.
+    ///
+    /// Passed to [`SessionBuilder::with_arg`](crate::session::SessionBuilder::with_arg),
+    /// the options become the session's default and are set on its
+    /// connection instead.
+    FormatOptions(FormatOptions),
     /// Custom argument.
     ///
     /// "--path=/tmp/chdb" translates into one of the following:
@@ -65,6 +71,11 @@ impl<'a> Arg<'a> {
             Self::LogLevel(v) => CString::new(format!("--log-level={}", v.as_str())),
             Self::OutputFormat(v) => CString::new(format!("--output-format={}", v.as_str())),
             Self::MultiQuery => CString::new("-n"),
+            Self::FormatOptions(_) => {
+                return Err(Error::InvalidData(
+                    "Format options cannot be passed as a command-line argument".to_string(),
+                ))
+            }
             Self::Custom(k, v) => match v {
                 None => CString::new(k.as_ref()),
                 Some(v) => CString::new(format!("--{k}={v}")),
@@ -84,6 +95,16 @@ impl<'a> Arg<'a> {
     }
 }
 
+/// Extract `FormatOptions` from a slice of `Arg`s.
+///
+/// Returns the first `FormatOptions` found, if any.
+pub(crate) fn extract_format_options<'a>(args: Option<&'a [Arg]>) -> Option<&'a FormatOptions> {
+    args?.iter().find_map(|a| match a {
+        Arg::FormatOptions(options) => Some(options),
+        _ => None,
+    })
+}
+
 /// Extract `OutputFormat` from a slice of `Arg`s.
 ///
 /// This function searches through the provided arguments and returns the first
@@ -0,0 +1,332 @@
+//! Backup and restore of session data with ClickHouse `BACKUP`/`RESTORE`.
+//!
+//! [`Session::backup_to`] writes a backup to a local directory or `.zip`
+//! archive with `BACKUP ... TO File(...)`, and [`Session::restore_from`]
+//! restores it with `RESTORE ... FROM File(...)`. [`BackupOptions`] limits
+//! either to some tables or databases, makes a backup incremental against a
+//! base backup, or runs the operation asynchronously; the outcome, or the
+//! progress of an asynchronous operation, is reported as a [`BackupInfo`].
+//!
+//! ClickHouse only allows `File()` backups under a configured directory, set
+//! with [`SessionBuilder::with_backup_dir`].
+//!
+//! [`SessionBuilder::with_backup_dir`]: crate::session::SessionBuilder::with_backup_dir
+//! [`Session::backup_to`]: crate::session::Session::backup_to
+//! [`Session::restore_from`]: crate::session::Session::restore_from
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::backup::BackupOptions;
+//! use chdb_rust::session::SessionBuilder;
+//!
+//! let session = SessionBuilder::new()
+//!     .with_data_path("/tmp/mydb")
+//!     .with_backup_dir("/tmp/backups")
+//!     .build()?;
+//!
+//! // A full backup, then one with only the changes since.
+//! let full = session.backup_to("/tmp/backups/full", None)?;
+//! let options = BackupOptions::new().with_base_backup("/tmp/backups/full");
+//! let incremental = session.backup_to("/tmp/backups/incremental", Some(&options))?;
+//! println!("{} -> {} bytes", full.total_size, incremental.total_size);
+//!
+//! // Restoring the incremental backup reads unchanged files from its base.
+//! let options = BackupOptions::new().with_table("default.events");
+//! session.execute("DROP TABLE default.events", None)?;
+//! session.restore_from("/tmp/backups/incremental", Some(&options))?;
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::json::Json;
+use crate::query_builder::{quote_identifier, quote_literal};
+
+/// What a backup or restore covers, and how it runs.
+///
+/// Without any tables or databases, everything except the system databases
+/// is covered.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupOptions {
+    targets: Vec<Target>,
+    base_backup: Option<PathBuf>,
+    asynchronous: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Table(String),
+    Database(String),
+}
+
+impl BackupOptions {
+    /// Create options covering all databases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cover a table, written as `table` or `database.table`.
+    pub fn with_table(mut self, table: impl Into<String>) -> Self {
+        self.targets.push(Target::Table(table.into()));
+        self
+    }
+
+    /// Cover a whole database.
+    pub fn with_database(mut self, database: impl Into<String>) -> Self {
+        self.targets.push(Target::Database(database.into()));
+        self
+    }
+
+    /// Make a backup incremental: only data missing from the backup at
+    /// `path` is written, and the base is read again when restoring.
+    ///
+    /// Ignored by restores.
+    pub fn with_base_backup(mut self, path: impl Into<PathBuf>) -> Self {
+        self.base_backup = Some(path.into());
+        self
+    }
+
+    /// Return as soon as the operation has started, with a
+    /// [`BackupInfo`] in a running state. Use
+    /// [`Session::backup_status`](crate::session::Session::backup_status)
+    /// to follow its progress.
+    pub fn asynchronous(mut self, value: bool) -> Self {
+        self.asynchronous = value;
+        self
+    }
+
+    /// The `BACKUP` statement writing to `path`.
+    pub(crate) fn backup_sql(&self, path: &Path) -> Result<String> {
+        let mut sql = format!("BACKUP {} TO {}", self.targets_sql(), file_sql(path)?);
+        if let Some(base) = &self.base_backup {
+            sql.push_str(&format!(" SETTINGS base_backup = {}", file_sql(base)?));
+        }
+        if self.asynchronous {
+            sql.push_str(" ASYNC");
+        }
+        Ok(sql)
+    }
+
+    /// The `RESTORE` statement reading from `path`.
+    pub(crate) fn restore_sql(&self, path: &Path) -> Result<String> {
+        let mut sql = format!("RESTORE {} FROM {}", self.targets_sql(), file_sql(path)?);
+        if self.asynchronous {
+            sql.push_str(" ASYNC");
+        }
+        Ok(sql)
+    }
+
+    fn targets_sql(&self) -> String {
+        if self.targets.is_empty() {
+            return "ALL".to_string();
+        }
+        self.targets
+            .iter()
+            .map(|target| match target {
+                Target::Table(table) => format!("TABLE {}", quote_identifier(table)),
+                Target::Database(database) => format!("DATABASE {}", quote_identifier(database)),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// `File('<absolute path>')`.
+fn file_sql(path: &Path) -> Result<String> {
+    let path = std::path::absolute(path)?;
+    Ok(format!(
+        "File({})",
+        quote_literal(path.to_str().ok_or(Error::PathError)?)
+    ))
+}
+
+/// The state of a backup or restore, as reported in `system.backups`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackupStatus {
+    /// The backup is being written.
+    CreatingBackup,
+    /// The backup was written.
+    BackupCreated,
+    /// The backup failed; see [`BackupInfo::error`].
+    BackupFailed,
+    /// The backup was cancelled.
+    BackupCancelled,
+    /// The backup is being restored.
+    Restoring,
+    /// The backup was restored.
+    Restored,
+    /// The restore failed; see [`BackupInfo::error`].
+    RestoreFailed,
+    /// The restore was cancelled.
+    RestoreCancelled,
+}
+
+impl BackupStatus {
+    /// The status name used by ClickHouse.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::CreatingBackup => "CREATING_BACKUP",
+            Self::BackupCreated => "BACKUP_CREATED",
+            Self::BackupFailed => "BACKUP_FAILED",
+            Self::BackupCancelled => "BACKUP_CANCELLED",
+            Self::Restoring => "RESTORING",
+            Self::Restored => "RESTORED",
+            Self::RestoreFailed => "RESTORE_FAILED",
+            Self::RestoreCancelled => "RESTORE_CANCELLED",
+        }
+    }
+
+    /// Whether the operation is still running.
+    pub fn is_running(self) -> bool {
+        matches!(self, Self::CreatingBackup | Self::Restoring)
+    }
+
+    /// Whether the operation completed successfully.
+    pub fn is_success(self) -> bool {
+        matches!(self, Self::BackupCreated | Self::Restored)
+    }
+}
+
+impl fmt::Display for BackupStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BackupStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        [
+            Self::CreatingBackup,
+            Self::BackupCreated,
+            Self::BackupFailed,
+            Self::BackupCancelled,
+            Self::Restoring,
+            Self::Restored,
+            Self::RestoreFailed,
+            Self::RestoreCancelled,
+        ]
+        .into_iter()
+        .find(|status| status.as_str() == s)
+        .ok_or_else(|| Error::InvalidData(format!("Unknown backup status '{s}'")))
+    }
+}
+
+/// A backup or restore operation, from `system.backups`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupInfo {
+    /// The operation id.
+    pub id: String,
+    /// The backup destination, e.g. `File('/tmp/backups/full')`.
+    pub name: String,
+    /// The current state.
+    pub status: BackupStatus,
+    /// The error message of a failed operation.
+    pub error: Option<String>,
+    /// Files in the backup, including those stored in a base backup.
+    pub num_files: u64,
+    /// Total size of those files, in bytes.
+    pub total_size: u64,
+    /// Files written to this backup, excluding those in a base backup.
+    pub num_entries: u64,
+    /// Bytes written to this backup, after compression.
+    pub compressed_size: u64,
+    /// Files read so far while restoring.
+    pub files_read: u64,
+    /// Bytes read so far while restoring.
+    pub bytes_read: u64,
+}
+
+impl BackupInfo {
+    /// The `system.backups` row of the operation `id`, as `JSONEachRow`.
+    pub(crate) fn select_sql(id: &str) -> String {
+        format!(
+            "SELECT id, name, status, error, num_files, total_size, num_entries, \
+             compressed_size, files_read, bytes_read FROM system.backups WHERE id = {}",
+            quote_literal(id)
+        )
+    }
+
+    /// Parse a `JSONEachRow` row of [`select_sql`](Self::select_sql).
+    pub(crate) fn parse(row: &str) -> Result<Self> {
+        let json = Json::parse(row)?;
+        let string = |key| json.get(key).and_then(Json::as_str).unwrap_or_default();
+        let number = |key| json.get(key).and_then(Json::as_u64).unwrap_or(0);
+        Ok(Self {
+            id: string("id").to_string(),
+            name: string("name").to_string(),
+            status: string("status").parse()?,
+            error: Some(string("error"))
+                .filter(|e| !e.is_empty())
+                .map(str::to_string),
+            num_files: number("num_files"),
+            total_size: number("total_size"),
+            num_entries: number("num_entries"),
+            compressed_size: number("compressed_size"),
+            files_read: number("files_read"),
+            bytes_read: number("bytes_read"),
+        })
+    }
+
+    /// The operation id in the `id\tstatus` row returned by `BACKUP` and
+    /// `RESTORE`.
+    pub(crate) fn parse_id(output: &str) -> Result<String> {
+        output
+            .split('\t')
+            .next()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| Error::InvalidData(format!("Unexpected backup output '{output}'")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_sql() {
+        let path = Path::new("/tmp/b");
+        assert_eq!(
+            BackupOptions::new().backup_sql(path).unwrap(),
+            "BACKUP ALL TO File('/tmp/b')"
+        );
+        let options = BackupOptions::new()
+            .with_table("db.events")
+            .with_database("logs")
+            .with_base_backup("/tmp/base")
+            .asynchronous(true);
+        assert_eq!(
+            options.backup_sql(path).unwrap(),
+            "BACKUP TABLE `db`.`events`, DATABASE `logs` TO File('/tmp/b') \
+             SETTINGS base_backup = File('/tmp/base') ASYNC"
+        );
+        assert_eq!(
+            options.restore_sql(path).unwrap(),
+            "RESTORE TABLE `db`.`events`, DATABASE `logs` FROM File('/tmp/b') ASYNC"
+        );
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info = BackupInfo::parse(
+            r#"{"id":"a1","name":"File('/tmp/b')","status":"BACKUP_CREATED","error":"","num_files":"12","total_size":"4096","num_entries":"3","compressed_size":"1024","files_read":"0","bytes_read":"0"}"#,
+        )
+        .unwrap();
+        assert_eq!(info.id, "a1");
+        assert_eq!(info.status, BackupStatus::BackupCreated);
+        assert!(info.status.is_success());
+        assert_eq!(info.error, None);
+        assert_eq!(info.num_files, 12);
+        assert_eq!(info.compressed_size, 1024);
+
+        assert!(BackupInfo::parse(r#"{"id":"a1","status":"NOPE"}"#).is_err());
+        assert_eq!(BackupInfo::parse_id("a1\tBACKUP_CREATED\n").unwrap(), "a1");
+    }
+}
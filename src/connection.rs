@@ -3,12 +3,23 @@
 //! This module provides the [`Connection`] type for managing connections to chDB databases.
 
 use std::ffi::{c_char, CString};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
 use crate::arrow_stream::{ArrowArray, ArrowSchema, ArrowStream};
 use crate::bindings;
 use crate::error::{Error, Result};
-use crate::format::OutputFormat;
+use crate::format::{split_extension, Compression, FormatOptions, OutputFormat};
+use crate::instrument::Instrument;
+use crate::library;
+use crate::progress::{Progress, ProgressTracker};
+use crate::query_builder::{quote_literal, split_statements};
+use crate::query_log::generate_query_id;
 use crate::query_result::QueryResult;
+use crate::stream::QueryStream;
 
 /// A connection to a chDB database.
 ///
@@ -19,8 +30,10 @@ use crate::query_result::QueryResult;
 /// # Thread Safety
 ///
 /// `Connection` implements `Send`, meaning it can be safely transferred between threads.
-/// However, the underlying chDB library may have limitations on concurrent access.
-/// It's recommended to use one connection per thread or implement proper synchronization.
+/// It is not `Sync`: chDB documents `chdb_query` as thread-safe, but not its
+/// streaming and Arrow APIs, and `SET`/`USE` statements change the connection
+/// for every caller. Share a connection between threads with a
+/// [`Pool`](crate::pool::Pool), or synchronize access to it.
 ///
 /// # Examples
 ///
@@ -42,8 +55,8 @@ pub struct Connection {
     inner: *mut bindings::chdb_connection,
 }
 
-// Safety: Connection is safe to send between threads
-// The underlying chDB library is thread-safe for query execution
+// Safety: Connection is safe to send between threads; the handle is not tied
+// to the thread that opened it.
 unsafe impl Send for Connection {}
 
 impl Connection {
@@ -70,8 +83,14 @@ impl Connection {
     /// # Errors
     ///
     /// Returns [`Error::ConnectionFailed`] if the
-    /// connection cannot be established.
+    /// connection cannot be established, or [`Error::IncompatibleLibrary`] if
+    /// the loaded libchdb is too old for these bindings. With the
+    /// `dynamic-loading` feature, returns [`Error::LibraryLoad`] if libchdb
+    /// cannot be loaded.
     pub fn open(args: &[&str]) -> Result<Self> {
+        #[cfg(feature = "dynamic-loading")]
+        crate::dynamic::ensure_loaded()?;
+
         let c_args: Vec<CString> = args
             .iter()
             .map(|s| CString::new(*s))
@@ -91,7 +110,9 @@ impl Connection {
             return Err(Error::ConnectionFailed);
         }
 
-        Ok(Self { inner: conn_ptr })
+        let conn = Self { inner: conn_ptr };
+        library::check_compatibility(&conn)?;
+        Ok(conn)
     }
 
     /// Connect to an in-memory database.
@@ -143,6 +164,11 @@ impl Connection {
         Self::open(&["clickhouse", &path_arg])
     }
 
+    /// The underlying `chdb_connection` handle.
+    pub(crate) fn raw(&self) -> bindings::chdb_connection {
+        unsafe { *self.inner }
+    }
+
     /// Execute a query and return the result.
     ///
     /// Executes a SQL query against the database and returns the result in the
@@ -177,6 +203,63 @@ impl Connection {
     /// - The query references non-existent tables or columns
     /// - The query execution fails for any other reason
     pub fn query(&self, sql: &str, format: OutputFormat) -> Result<QueryResult> {
+        let instrument = Instrument::query(sql, format);
+        let result = instrument.in_scope(|| self.run_query(sql, format));
+        instrument.finish_query(&result);
+        result
+    }
+
+    /// Execute a query tagged with a query id.
+    ///
+    /// The id is `query_id` if given, or a generated UUID otherwise, and is
+    /// available from [`QueryResult::query_id`]. Pass it to
+    /// [`Session::query_log`](crate::session::Session::query_log) to read
+    /// the query's profile from `system.query_log`.
+    ///
+    /// The id is passed in the query's own `log_comment` setting, added to
+    /// its `SETTINGS` clause like [`FormatOptions`]. No connection state is changed, so queries tagged
+    /// concurrently on a shared connection keep their own ids, and a
+    /// `log_comment` set earlier with `SET` still applies to other queries.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::OutputFormat;
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// let result = conn.query_with_id("SELECT 1", OutputFormat::CSV, Some("report-42"))?;
+    /// assert_eq!(result.query_id(), Some("report-42"));
+    ///
+    /// let result = conn.query_with_id("SELECT 1", OutputFormat::CSV, None)?;
+    /// println!("query id: {}", result.query_id().unwrap());
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    pub fn query_with_id(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        query_id: Option<&str>,
+    ) -> Result<QueryResult> {
+        let query_id = query_id.map_or_else(generate_query_id, str::to_string);
+        let instrument = Instrument::query(sql, format);
+        instrument.record_query_id(&query_id);
+        let tagged = FormatOptions::new()
+            .setting("log_comment", query_id.as_str())
+            .apply(sql)?;
+        let result = instrument.in_scope(|| {
+            self.run_query(&tagged, format)
+                .map(|result| result.with_query_id(query_id))
+        });
+        instrument.finish_query(&result);
+        result
+    }
+
+    fn run_query(&self, sql: &str, format: OutputFormat) -> Result<QueryResult> {
         let query_cstr = CString::new(sql)?;
         let format_cstr = CString::new(format.as_str())?;
 
@@ -193,6 +276,356 @@ impl Connection {
         result.check_error()
     }
 
+    /// Execute a query with per-query [`FormatOptions`].
+    ///
+    /// The options are added to the `SETTINGS` clause of `sql`, which is
+    /// appended if the query does not have one.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::{FormatOptions, OutputFormat};
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// let options = FormatOptions::new().csv_delimiter(';');
+    /// let result = conn.query_with_options("SELECT 1, 2", OutputFormat::CSV, &options)?;
+    /// assert_eq!(result.data_utf8_lossy(), "1;2\n");
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a setting is unknown to chDB.
+    pub fn query_with_options(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        options: &FormatOptions,
+    ) -> Result<QueryResult> {
+        self.query(&options.apply(sql)?, format)
+    }
+
+    /// Execute a query and stream its result chunk by chunk.
+    ///
+    /// Unlike [`query`](Self::query), the result is not materialized in memory
+    /// at once; each item of the returned [`QueryStream`] is one chunk of output
+    /// in the requested format.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::OutputFormat;
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// for chunk in conn.query_stream("SELECT number FROM numbers(1000000)", OutputFormat::CSV)? {
+    ///     println!("{} bytes", chunk?.data_ref().len());
+    /// }
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query cannot be started. Errors raised while the
+    /// query runs are returned by the stream's iterator.
+    pub fn query_stream(&self, sql: &str, format: OutputFormat) -> Result<QueryStream<'_>> {
+        let instrument = Instrument::stream(sql, format);
+        let result_ptr = instrument.in_scope(|| -> Result<_> {
+            let query_cstr = CString::new(sql)?;
+            let format_cstr = CString::new(format.as_str())?;
+            Ok(unsafe {
+                bindings::chdb_stream_query(self.raw(), query_cstr.as_ptr(), format_cstr.as_ptr())
+            })
+        });
+        match result_ptr {
+            Ok(result_ptr) => QueryStream::new(self, result_ptr, instrument),
+            Err(e) => {
+                instrument.fail(&e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Execute a query, reporting its progress while it runs.
+    ///
+    /// The query is streamed, and `on_progress` is called with the rows and
+    /// bytes read so far. With a `*WithProgress` format such as
+    /// [`OutputFormat::JSONEachRowWithProgress`], progress is taken from the
+    /// progress rows, which include the total number of rows to read and are
+    /// removed from the returned output. With other formats, progress is
+    /// reported once per chunk and the total is unknown. See
+    /// [`progress`](crate::progress).
+    ///
+    /// # Returns
+    ///
+    /// Returns the complete output in the requested format.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::OutputFormat;
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// let output = conn.query_with_progress(
+    ///     "SELECT sum(number) FROM numbers(1000000000)",
+    ///     OutputFormat::JSONEachRowWithProgress,
+    ///     |progress| {
+    ///         if let Some(done) = progress.fraction() {
+    ///             eprint!("\r{:3.0}% after {:?}", done * 100.0, progress.elapsed);
+    ///         }
+    ///     },
+    /// )?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails. Progress reported before the
+    /// failure is not retracted.
+    pub fn query_with_progress(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        mut on_progress: impl FnMut(Progress),
+    ) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let mut tracker = ProgressTracker::new(format);
+        for chunk in self.query_stream(sql, format)? {
+            tracker.chunk(&chunk?, start.elapsed(), &mut on_progress);
+        }
+        Ok(tracker.finish(start.elapsed(), &mut on_progress))
+    }
+
+    /// Execute a query and stream its result into a writer.
+    ///
+    /// Chunks are written to `writer` as they arrive, so arbitrarily large
+    /// results can be exported without buffering them in memory.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL query string to execute
+    /// * `format` - The output format written to the sink
+    /// * `writer` - The sink, e.g. a file, socket or `Vec<u8>`
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::OutputFormat;
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// let stdout = std::io::stdout();
+    /// conn.query_to_writer("SELECT number FROM numbers(10)", OutputFormat::CSV, stdout.lock())?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or writing to the sink fails. Data
+    /// written before the failure is not rolled back.
+    pub fn query_to_writer(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        mut writer: impl Write,
+    ) -> Result<u64> {
+        let mut written = 0u64;
+        for chunk in self.query_stream(sql, format)? {
+            let chunk = chunk?;
+            writer.write_all(chunk.data_ref())?;
+            written += chunk.data_ref().len() as u64;
+        }
+        writer.flush()?;
+        Ok(written)
+    }
+
+    /// Execute a query and write its result to a file.
+    ///
+    /// The output format is inferred from the file extension (e.g. `.csv`,
+    /// `.parquet`, `.ndjson`, `.orc`, `.avro`). A trailing compression suffix
+    /// such as `.gz` or `.zst` makes chDB compress the output, using
+    /// `INTO OUTFILE ... COMPRESSION`. Existing files are overwritten.
+    ///
+    /// With compression, `sql` must be a single query without its own
+    /// `INTO OUTFILE` or `FORMAT` clause, since those are added to it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// conn.query_to_file("SELECT number FROM numbers(10)", "/tmp/numbers.parquet")?;
+    /// conn.query_to_file("SELECT number FROM numbers(10)", "/tmp/numbers.csv.gz")?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] if the format cannot be inferred from the
+    /// extension, [`Error::PathError`] if the path is not valid UTF-8, or an
+    /// error if the query or the file write fails.
+    pub fn query_to_file(&self, sql: &str, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let path_str = path.to_str().ok_or(Error::PathError)?;
+        let (format, compression) = split_extension(path_str)
+            .and_then(|(ext, compression)| Some((OutputFormat::from_extension(ext)?, compression)))
+            .ok_or_else(|| {
+                Error::InvalidData(format!("Cannot infer output format from '{path_str}'"))
+            })?;
+
+        match compression {
+            None => {
+                let file = BufWriter::new(File::create(path)?);
+                self.query_to_writer(sql, format, file)?;
+            }
+            Some(compression) => {
+                self.query_into_outfile(sql, path_str, format, compression, None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Execute a query and write its compressed result to a file.
+    ///
+    /// Unlike [`query_to_file`](Self::query_to_file), the format and
+    /// compression method are given explicitly, so the file name is not
+    /// interpreted. chDB compresses the output while writing it.
+    ///
+    /// `sql` must be a single query without its own `INTO OUTFILE` or
+    /// `FORMAT` clause, since those are added to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The SQL query string to execute
+    /// * `path` - The file to write; an existing file is overwritten
+    /// * `format` - The output format
+    /// * `compression` - The compression method
+    /// * `level` - The compression level, or `None` for the method's default
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::{Compression, OutputFormat};
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// conn.query_to_file_compressed(
+    ///     "SELECT number FROM numbers(1000000)",
+    ///     "/tmp/numbers.ndjson.zst",
+    ///     OutputFormat::JSONEachRow,
+    ///     Compression::Zstd,
+    ///     Some(9),
+    /// )?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PathError`] if the path is not valid UTF-8,
+    /// [`Error::InvalidData`] if `sql` holds several statements, or an error
+    /// if the query fails or the level is out of range for the method.
+    pub fn query_to_file_compressed(
+        &self,
+        sql: &str,
+        path: impl AsRef<Path>,
+        format: OutputFormat,
+        compression: Compression,
+        level: Option<u32>,
+    ) -> Result<()> {
+        let path_str = path.as_ref().to_str().ok_or(Error::PathError)?;
+        self.query_into_outfile(sql, path_str, format, compression, level)
+    }
+
+    /// Execute a query and return its result compressed.
+    ///
+    /// The result is compressed by chDB into a file in a new private
+    /// temporary directory, which is read back and removed, so the returned
+    /// bytes are a complete compressed stream (e.g. a `.gz` member) that can
+    /// be stored or sent as-is. Like for
+    /// [`query_to_file_compressed`](Self::query_to_file_compressed), `sql`
+    /// must be a single query without its own `INTO OUTFILE` or `FORMAT`
+    /// clause.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::format::{Compression, OutputFormat};
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// let gz = conn.query_compressed(
+    ///     "SELECT number FROM numbers(1000)",
+    ///     OutputFormat::CSV,
+    ///     Compression::Gzip,
+    ///     None,
+    /// )?;
+    /// std::fs::write("/tmp/numbers.csv.gz", gz)?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or the temporary file cannot be
+    /// written or read.
+    pub fn query_compressed(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        compression: Compression,
+        level: Option<u32>,
+    ) -> Result<Vec<u8>> {
+        let dir = private_temp_dir()?;
+        let path = dir.join(format!("result.{}", compression.file_extension()));
+        // The file is created here, so that chDB only ever truncates a file
+        // this process owns.
+        let result = File::options()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .map_err(Error::from)
+            .and_then(|_| {
+                let path_str = path.to_str().ok_or(Error::PathError)?;
+                self.query_into_outfile(sql, path_str, format, compression, level)
+            })
+            .and_then(|()| Ok(fs::read(&path)?));
+        let _ = fs::remove_dir_all(&dir);
+        result
+    }
+
+    fn query_into_outfile(
+        &self,
+        sql: &str,
+        path: &str,
+        format: OutputFormat,
+        compression: Compression,
+        level: Option<u32>,
+    ) -> Result<()> {
+        let [statement] = split_statements(sql)[..] else {
+            return Err(Error::InvalidData(
+                "Expected a single query to write to a file".to_string(),
+            ));
+        };
+        // On its own line, so that a trailing `--` comment cannot swallow it.
+        let mut sql = format!(
+            "{statement}\nINTO OUTFILE {} TRUNCATE COMPRESSION {}",
+            quote_literal(path),
+            quote_literal(compression.as_str()),
+        );
+        if let Some(level) = level {
+            sql.push_str(&format!(" LEVEL {level}"));
+        }
+        sql.push_str(&format!(" FORMAT {}", format.as_str()));
+        self.query(&sql, format)?;
+        Ok(())
+    }
+
     /// Register an Arrow stream as a table function with the given name.
     ///
     /// This function registers an Arrow stream as a virtual table that can be queried
@@ -227,6 +660,7 @@ impl Connection {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The loaded libchdb predates the Arrow scan API ([`Error::Unsupported`])
     /// - The table name contains invalid characters
     /// - The Arrow stream handle is invalid
     /// - Registration fails for any other reason
@@ -235,21 +669,27 @@ impl Connection {
         table_name: &str,
         arrow_stream: &ArrowStream,
     ) -> Result<()> {
-        let table_name_cstr = CString::new(table_name)?;
-        let conn = unsafe { *self.inner };
+        let instrument = Instrument::arrow("register", table_name);
+        let result = instrument.in_scope(|| {
+            library::require("Arrow scan", library::ARROW_SCAN_MIN_VERSION)?;
+            let table_name_cstr = CString::new(table_name)?;
+            let conn = unsafe { *self.inner };
 
-        let state = unsafe {
-            bindings::chdb_arrow_scan(conn, table_name_cstr.as_ptr(), arrow_stream.as_raw())
-        };
+            let state = unsafe {
+                bindings::chdb_arrow_scan(conn, table_name_cstr.as_ptr(), arrow_stream.as_raw())
+            };
 
-        if state == bindings::chdb_state_CHDBSuccess {
-            Ok(())
-        } else {
-            Err(Error::QueryError(format!(
-                "Failed to register Arrow stream as table '{}'",
-                table_name
-            )))
-        }
+            if state == bindings::chdb_state_CHDBSuccess {
+                Ok(())
+            } else {
+                Err(Error::QueryError(format!(
+                    "Failed to register Arrow stream as table '{}'",
+                    table_name
+                )))
+            }
+        });
+        instrument.finish(&result);
+        result
     }
 
     /// Register an Arrow array as a table function with the given name.
@@ -289,6 +729,7 @@ impl Connection {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The loaded libchdb predates the Arrow scan API ([`Error::Unsupported`])
     /// - The table name contains invalid characters
     /// - The Arrow schema or array handles are invalid
     /// - Registration fails for any other reason
@@ -298,26 +739,32 @@ impl Connection {
         arrow_schema: &ArrowSchema,
         arrow_array: &ArrowArray,
     ) -> Result<()> {
-        let table_name_cstr = CString::new(table_name)?;
-        let conn = unsafe { *self.inner };
+        let instrument = Instrument::arrow("register", table_name);
+        let result = instrument.in_scope(|| {
+            library::require("Arrow scan", library::ARROW_SCAN_MIN_VERSION)?;
+            let table_name_cstr = CString::new(table_name)?;
+            let conn = unsafe { *self.inner };
 
-        let state = unsafe {
-            bindings::chdb_arrow_array_scan(
-                conn,
-                table_name_cstr.as_ptr(),
-                arrow_schema.as_raw(),
-                arrow_array.as_raw(),
-            )
-        };
+            let state = unsafe {
+                bindings::chdb_arrow_array_scan(
+                    conn,
+                    table_name_cstr.as_ptr(),
+                    arrow_schema.as_raw(),
+                    arrow_array.as_raw(),
+                )
+            };
 
-        if state == bindings::chdb_state_CHDBSuccess {
-            Ok(())
-        } else {
-            Err(Error::QueryError(format!(
-                "Failed to register Arrow array as table '{}'",
-                table_name
-            )))
-        }
+            if state == bindings::chdb_state_CHDBSuccess {
+                Ok(())
+            } else {
+                Err(Error::QueryError(format!(
+                    "Failed to register Arrow array as table '{}'",
+                    table_name
+                )))
+            }
+        });
+        instrument.finish(&result);
+        result
     }
 
     /// Unregister an Arrow stream table function that was previously registered.
@@ -355,24 +802,31 @@ impl Connection {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The loaded libchdb predates the Arrow scan API ([`Error::Unsupported`])
     /// - The table name contains invalid characters
     /// - The table was not previously registered
     /// - Unregistration fails for any other reason
     pub fn unregister_arrow_table(&self, table_name: &str) -> Result<()> {
-        let table_name_cstr = CString::new(table_name)?;
-        let conn = unsafe { *self.inner };
+        let instrument = Instrument::arrow("unregister", table_name);
+        let result = instrument.in_scope(|| {
+            library::require("Arrow scan", library::ARROW_SCAN_MIN_VERSION)?;
+            let table_name_cstr = CString::new(table_name)?;
+            let conn = unsafe { *self.inner };
 
-        let state =
-            unsafe { bindings::chdb_arrow_unregister_table(conn, table_name_cstr.as_ptr()) };
+            let state =
+                unsafe { bindings::chdb_arrow_unregister_table(conn, table_name_cstr.as_ptr()) };
 
-        if state == bindings::chdb_state_CHDBSuccess {
-            Ok(())
-        } else {
-            Err(Error::QueryError(format!(
-                "Failed to unregister Arrow table '{}'",
-                table_name
-            )))
-        }
+            if state == bindings::chdb_state_CHDBSuccess {
+                Ok(())
+            } else {
+                Err(Error::QueryError(format!(
+                    "Failed to unregister Arrow table '{}'",
+                    table_name
+                )))
+            }
+        });
+        instrument.finish(&result);
+        result
     }
 }
 
@@ -383,3 +837,25 @@ impl Drop for Connection {
         }
     }
 }
+
+/// Create a new directory under the system's temporary directory that only
+/// the current user can access.
+fn private_temp_dir() -> Result<std::path::PathBuf> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    loop {
+        let path = std::env::temp_dir().join(format!(
+            "chdb-rust-{}-{}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        match builder.create(&path) {
+            Ok(()) => return Ok(path),
+            // Left behind by an earlier process with the same pid, or planted.
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+//! Advisory locking of session data directories.
+//!
+//! chDB does not guard against two processes opening the same data directory,
+//! which corrupts it. [`DirLock`] holds an OS file lock on a lock file inside
+//! the directory for the lifetime of a session: exclusive for writable
+//! sessions, shared for read-only ones. The exclusive holder writes its pid
+//! to the file so that others can report who holds it.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+
+/// The name of the lock file in the data directory.
+pub(crate) const LOCK_FILE_NAME: &str = "chdb-rust.lock";
+
+/// How often a contended lock is retried while waiting.
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A held lock on a data directory, released on drop.
+#[derive(Debug)]
+pub(crate) struct DirLock {
+    file: File,
+    shared: bool,
+}
+
+impl DirLock {
+    /// Lock `dir`, retrying for up to `timeout` while another process holds
+    /// a conflicting lock.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::DataDirLocked`] if the lock is still held after
+    /// `timeout`, or an I/O error if the lock file cannot be opened.
+    pub(crate) fn acquire(dir: &Path, shared: bool, timeout: Duration) -> Result<Self> {
+        Self::acquire_file(&dir.join(LOCK_FILE_NAME), shared, timeout)
+    }
+
+    /// Lock the lock file at `path`, creating it if needed; see
+    /// [`acquire`](Self::acquire).
+    pub(crate) fn acquire_file(path: &Path, shared: bool, timeout: Duration) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            let locked = if shared {
+                file.try_lock_shared()
+            } else {
+                file.try_lock()
+            };
+            match locked {
+                Ok(()) => break,
+                Err(TryLockError::WouldBlock) if Instant::now() < deadline => {
+                    thread::sleep(RETRY_INTERVAL.min(deadline - Instant::now()));
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(Error::DataDirLocked {
+                        pid: read_pid(&mut file),
+                    })
+                }
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+            }
+        }
+        if !shared {
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            write!(file, "{}", std::process::id())?;
+            file.flush()?;
+        }
+        Ok(Self { file, shared })
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        // Don't leave a stale pid behind for later shared holders.
+        if !self.shared {
+            self.file.set_len(0).ok();
+        }
+    }
+}
+
+/// The pid written by the exclusive holder, if any.
+fn read_pid(file: &mut File) -> Option<u32> {
+    let mut contents = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive_lock() {
+        let dir = tempdir::TempDir::new("chdb-lock").unwrap();
+        let lock = DirLock::acquire(dir.path(), false, Duration::ZERO).unwrap();
+
+        let err = DirLock::acquire(dir.path(), false, Duration::from_millis(100)).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DataDirLocked { pid: Some(pid) } if pid == std::process::id()
+        ));
+        assert!(DirLock::acquire(dir.path(), true, Duration::ZERO).is_err());
+
+        drop(lock);
+        DirLock::acquire(dir.path(), false, Duration::ZERO).unwrap();
+    }
+
+    #[test]
+    fn test_shared_lock() {
+        let dir = tempdir::TempDir::new("chdb-lock").unwrap();
+        let first = DirLock::acquire(dir.path(), true, Duration::ZERO).unwrap();
+        let second = DirLock::acquire(dir.path(), true, Duration::ZERO).unwrap();
+        assert!(DirLock::acquire(dir.path(), false, Duration::ZERO).is_err());
+        drop((first, second));
+
+        // A waiting writer gets the lock once the readers are gone.
+        let reader = DirLock::acquire(dir.path(), true, Duration::ZERO).unwrap();
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            drop(reader);
+        });
+        DirLock::acquire(dir.path(), false, Duration::from_secs(10)).unwrap();
+        releaser.join().unwrap();
+    }
+}
@@ -0,0 +1,252 @@
+//! Loading libchdb at runtime instead of linking it at build time.
+//!
+//! With the `dynamic-loading` feature, the crate does not link against
+//! libchdb. The library is opened with `dlopen` the first time a
+//! [`Connection`](crate::connection::Connection) is opened, or earlier with an
+//! explicit [`load_library`] call, and every `chdb_*` function of `chdb.h` is
+//! resolved from it. The rest of the API is unchanged. The functions the crate
+//! does not call, such as `chdb_query_cmdline` or the deprecated
+//! `chdb_streaming_*` API, are resolved if present but not required; the
+//! legacy entry points without a `chdb_` prefix (`query_stable`,
+//! `connect_chdb`, `query_conn`, ...) are not resolved.
+//!
+//! Without an explicit call, the path is taken from the `CHDB_LIBRARY_PATH`
+//! environment variable, falling back to `libchdb.so` (`libchdb.dylib` on
+//! macOS) on the default library search path.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::connection::Connection;
+//! use chdb_rust::format::OutputFormat;
+//!
+//! chdb_rust::load_library("/opt/chdb/lib/libchdb.so")?;
+//! let conn = Connection::open_in_memory()?;
+//! let result = conn.query("SELECT 1", OutputFormat::CSV)?;
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use libloading::Library;
+
+use crate::error::{Error, Result};
+
+/// Environment variable consulted when no library was loaded explicitly.
+pub const LIBRARY_PATH_ENV: &str = "CHDB_LIBRARY_PATH";
+
+struct Loaded {
+    path: PathBuf,
+    functions: bindings::Functions,
+    // Keeps the symbols in `functions` valid; never unloaded.
+    _library: Library,
+}
+
+static LOADED: OnceLock<Loaded> = OnceLock::new();
+static LOAD_LOCK: Mutex<()> = Mutex::new(());
+
+/// Load libchdb from `path` and resolve its functions.
+///
+/// Only one library can be loaded per process. Loading the same path again
+/// is a no-op; loading a different one fails.
+///
+/// # Errors
+///
+/// Returns [`Error::LibraryLoad`] if the library cannot be opened, a required
+/// symbol is missing, or a different library is already loaded.
+pub fn load_library(path: impl AsRef<Path>) -> Result<()> {
+    load(path.as_ref().as_os_str().to_os_string()).map(|_| ())
+}
+
+/// The path of the loaded library, if any.
+pub(crate) fn loaded_path() -> Option<&'static Path> {
+    LOADED.get().map(|loaded| loaded.path.as_path())
+}
+
+/// Make sure a library is loaded, loading the default one if necessary.
+pub(crate) fn ensure_loaded() -> Result<()> {
+    if LOADED.get().is_some() {
+        return Ok(());
+    }
+    let path =
+        std::env::var_os(LIBRARY_PATH_ENV).unwrap_or_else(|| libloading::library_filename("chdb"));
+    load(path).map(|_| ())
+}
+
+fn load(path: OsString) -> Result<&'static Loaded> {
+    let _guard = LOAD_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let path = PathBuf::from(path);
+    if let Some(loaded) = LOADED.get() {
+        if loaded.path == path {
+            return Ok(loaded);
+        }
+        return Err(Error::LibraryLoad(format!(
+            "{} is already loaded, cannot load {}",
+            loaded.path.display(),
+            path.display()
+        )));
+    }
+
+    let error = |e: libloading::Error| {
+        Error::LibraryLoad(format!(
+            "Failed to load libchdb from {}: {e}",
+            path.display()
+        ))
+    };
+    let library = unsafe { Library::new(&path) }.map_err(error)?;
+    let functions = unsafe { bindings::Functions::load(&library) }.map_err(error)?;
+    Ok(LOADED.get_or_init(|| Loaded {
+        path,
+        functions,
+        _library: library,
+    }))
+}
+
+fn functions() -> &'static bindings::Functions {
+    // Every FFI call goes through a `Connection`, whose constructor loads the library.
+    &LOADED
+        .get()
+        .expect("libchdb is not loaded; open a Connection or call load_library first")
+        .functions
+}
+
+/// Declare functions resolved from the loaded library, as drop-in
+/// replacements for the `extern "C"` declarations of the generated bindings.
+///
+/// Functions in the `optional` group may be missing from the loaded library,
+/// either because they are newer than the oldest supported release or
+/// because the crate does not use them. Calling a missing one panics, so
+/// callers must check the version before using them.
+macro_rules! dynamic_functions {
+    (
+        required { $(fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)* }
+        optional { $(fn $opt_name:ident($($opt_arg:ident: $opt_ty:ty),* $(,)?) $(-> $opt_ret:ty)?;)* }
+    ) => {
+        pub(crate) struct Functions {
+            $($name: unsafe extern "C" fn($($ty),*) $(-> $ret)?,)*
+            $($opt_name: Option<unsafe extern "C" fn($($opt_ty),*) $(-> $opt_ret)?>,)*
+        }
+
+        impl Functions {
+            pub(super) unsafe fn load(library: &Library) -> Result<Self, libloading::Error> {
+                Ok(Self {
+                    $($name: *library.get(concat!(stringify!($name), "\0").as_bytes())?,)*
+                    $($opt_name: library
+                        .get(concat!(stringify!($opt_name), "\0").as_bytes())
+                        .ok()
+                        .map(|symbol| *symbol),)*
+                })
+            }
+        }
+
+        $(
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                (super::functions().$name)($($arg),*)
+            }
+        )*
+        $(
+            pub unsafe fn $opt_name($($opt_arg: $opt_ty),*) $(-> $opt_ret)? {
+                let f = super::functions().$opt_name.unwrap_or_else(|| {
+                    panic!(concat!(stringify!($opt_name), " is not provided by the loaded libchdb"))
+                });
+                f($($opt_arg),*)
+            }
+        )*
+    };
+}
+
+/// The generated types, with the `extern "C"` functions shadowed by
+/// functions resolved at runtime.
+#[allow(
+    dead_code,
+    unused,
+    non_snake_case,
+    non_camel_case_types,
+    non_upper_case_globals,
+    clippy::missing_safety_doc
+)]
+pub(crate) mod bindings {
+    use std::os::raw::{c_char, c_int};
+
+    use libloading::Library;
+
+    mod generated {
+        include!("bindings.rs");
+    }
+    pub use generated::*;
+
+    dynamic_functions! {
+        required {
+            fn chdb_connect(argc: c_int, argv: *mut *mut c_char) -> *mut chdb_connection;
+            fn chdb_close_conn(conn: *mut chdb_connection);
+            fn chdb_query(
+                conn: chdb_connection,
+                query: *const c_char,
+                format: *const c_char,
+            ) -> *mut chdb_result;
+            fn chdb_query_n(
+                conn: chdb_connection,
+                query: *const c_char,
+                query_len: usize,
+                format: *const c_char,
+                format_len: usize,
+            ) -> *mut chdb_result;
+            fn chdb_stream_query(
+                conn: chdb_connection,
+                query: *const c_char,
+                format: *const c_char,
+            ) -> *mut chdb_result;
+            fn chdb_stream_fetch_result(
+                conn: chdb_connection,
+                result: *mut chdb_result,
+            ) -> *mut chdb_result;
+            fn chdb_stream_cancel_query(conn: chdb_connection, result: *mut chdb_result);
+            fn chdb_destroy_query_result(result: *mut chdb_result);
+            fn chdb_result_buffer(result: *mut chdb_result) -> *mut c_char;
+            fn chdb_result_length(result: *mut chdb_result) -> usize;
+            fn chdb_result_elapsed(result: *mut chdb_result) -> f64;
+            fn chdb_result_rows_read(result: *mut chdb_result) -> u64;
+            fn chdb_result_bytes_read(result: *mut chdb_result) -> u64;
+            fn chdb_result_storage_rows_read(result: *mut chdb_result) -> u64;
+            fn chdb_result_storage_bytes_read(result: *mut chdb_result) -> u64;
+            fn chdb_result_error(result: *mut chdb_result) -> *const c_char;
+        }
+        optional {
+            fn chdb_arrow_scan(
+                conn: chdb_connection,
+                table_name: *const c_char,
+                arrow_stream: chdb_arrow_stream,
+            ) -> chdb_state;
+            fn chdb_arrow_array_scan(
+                conn: chdb_connection,
+                table_name: *const c_char,
+                arrow_schema: chdb_arrow_schema,
+                arrow_array: chdb_arrow_array,
+            ) -> chdb_state;
+            fn chdb_arrow_unregister_table(
+                conn: chdb_connection,
+                table_name: *const c_char,
+            ) -> chdb_state;
+            fn chdb_query_cmdline(argc: c_int, argv: *mut *mut c_char) -> *mut chdb_result;
+            fn chdb_stream_query_n(
+                conn: chdb_connection,
+                query: *const c_char,
+                query_len: usize,
+                format: *const c_char,
+                format_len: usize,
+            ) -> *mut chdb_result;
+            fn chdb_streaming_result_error(result: *mut chdb_streaming_result) -> *const c_char;
+            fn chdb_streaming_fetch_result(
+                conn: *mut chdb_conn,
+                result: *mut chdb_streaming_result,
+            ) -> *mut local_result_v2;
+            fn chdb_streaming_cancel_query(
+                conn: *mut chdb_conn,
+                result: *mut chdb_streaming_result,
+            );
+            fn chdb_destroy_result(result: *mut chdb_streaming_result);
+        }
+    }
+}
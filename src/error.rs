@@ -3,6 +3,7 @@
 //! This module defines the error types used throughout the crate.
 
 use std::ffi::NulError;
+use std::fmt;
 use std::string::FromUtf8Error;
 
 /// Errors that can occur when using chdb-rust.
@@ -45,9 +46,99 @@ pub enum Error {
     /// which typically includes details about SQL syntax errors, missing tables, etc.
     #[error("{0}")]
     QueryError(String),
+    /// A format name is not known to chDB.
+    #[error("Unknown format: {0}")]
+    UnknownFormat(String),
+    /// An already-applied migration no longer matches its recorded checksum.
+    #[error("Checksum mismatch for applied migration {version} ({name})")]
+    MigrationChecksumMismatch { version: u64, name: String },
+    /// The database records a migration version that is not known locally.
+    #[error("Unknown applied migration version {0}")]
+    UnknownMigration(u64),
+    /// libchdb could not be loaded at runtime.
+    #[error("{0}")]
+    LibraryLoad(String),
+    /// The loaded libchdb is too old for these bindings.
+    #[error("libchdb {found} is not supported, {required} or newer is required")]
+    IncompatibleLibrary { found: String, required: String },
+    /// The loaded libchdb does not provide an API.
+    #[error("{feature} requires libchdb {required} or newer, found {found}")]
+    Unsupported {
+        feature: &'static str,
+        required: String,
+        found: String,
+    },
+    /// A statement that may modify data was run on a read-only session.
+    #[error("Session is read-only, {0} statements are not allowed")]
+    ReadOnly(String),
+    /// Another process holds the lock on a session's data directory.
+    #[error("Data directory is locked by {}", .pid.map_or("another process".to_string(), |pid| format!("process {pid}")))]
+    DataDirLocked {
+        /// The pid of the process holding the lock, if known.
+        pid: Option<u32>,
+    },
+    /// No snapshot of this name has been saved.
+    #[error("Snapshot not found: {0}")]
+    SnapshotNotFound(String),
+    /// No [`Pool`](crate::pool::Pool) slot became free within the wait limit.
+    #[error("Timed out after {0:?} waiting for a pooled connection")]
+    PoolTimeout(std::time::Duration),
+}
+
+impl Error {
+    /// The ClickHouse error code of a [`QueryError`](Self::QueryError), if its
+    /// message carries one (`Code: 60. DB::Exception: ...`).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::connection::Connection;
+    /// use chdb_rust::error::ErrorCode;
+    /// use chdb_rust::format::OutputFormat;
+    ///
+    /// let conn = Connection::open_in_memory()?;
+    /// let err = conn.query("SELECT * FROM missing", OutputFormat::CSV).unwrap_err();
+    /// assert_eq!(err.code(), Some(ErrorCode(60)));
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn code(&self) -> Option<ErrorCode> {
+        let Self::QueryError(message) = self else {
+            return None;
+        };
+        let (_, rest) = message.split_once("Code: ")?;
+        let end = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        rest[..end].parse().ok().map(ErrorCode)
+    }
+}
+
+/// A ClickHouse error code, such as `60` (`UNKNOWN_TABLE`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ErrorCode(pub u32);
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
 }
 
 /// A type alias for `Result<T, Error>`.
 ///
 /// This is the standard result type used throughout the crate.
 pub type Result<T, Err = Error> = std::result::Result<T, Err>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_code() {
+        let err = Error::QueryError(
+            "Code: 60. DB::Exception: Unknown table expression identifier 'missing'".to_string(),
+        );
+        assert_eq!(err.code(), Some(ErrorCode(60)));
+        assert_eq!(Error::QueryError("boom".to_string()).code(), None);
+        assert_eq!(Error::NoResult.code(), None);
+    }
+}
@@ -0,0 +1,363 @@
+//! Structured `EXPLAIN` output.
+//!
+//! [`Session::explain`](crate::session::Session::explain) runs one of the
+//! `EXPLAIN` variants selected by [`ExplainKind`] and returns an [`Explain`]:
+//! a parsed [`PlanNode`] tree for query plans, per-table [`Estimate`]s for
+//! `EXPLAIN ESTIMATE`, and lines of text for the others.
+//!
+//! Query plans are requested with `json = 1, indexes = 1`, so each
+//! `ReadFromMergeTree` step lists the indexes used to prune parts and
+//! granules as [`IndexUsage`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::explain::ExplainKind;
+//! use chdb_rust::session::SessionBuilder;
+//!
+//! let session = SessionBuilder::new().with_data_path("/tmp/mydb").build()?;
+//! session.execute(
+//!     "CREATE TABLE IF NOT EXISTS events (id UInt64, kind String) ENGINE = MergeTree ORDER BY id",
+//!     None,
+//! )?;
+//! session.execute("INSERT INTO events SELECT number, 'click' FROM numbers(100000)", None)?;
+//!
+//! let explain = session.explain("SELECT * FROM events WHERE id = 42", ExplainKind::Plan)?;
+//! let plan = explain.plan().unwrap();
+//! assert!(plan.uses_primary_key());
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::json::Json;
+
+/// The `EXPLAIN` variant to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExplainKind {
+    /// `EXPLAIN AST`: the parsed syntax tree.
+    Ast,
+    /// `EXPLAIN SYNTAX`: the query after syntax-level optimizations.
+    Syntax,
+    /// `EXPLAIN PLAN`: the query plan, with index usage.
+    Plan,
+    /// `EXPLAIN PIPELINE`: the processors executing the query.
+    Pipeline,
+    /// `EXPLAIN ESTIMATE`: the parts, rows and marks to be read per table.
+    Estimate,
+    /// `EXPLAIN QUERY TREE`: the analyzed query tree.
+    QueryTree,
+}
+
+impl ExplainKind {
+    /// The `EXPLAIN` clause preceding the query.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Ast => "EXPLAIN AST",
+            Self::Syntax => "EXPLAIN SYNTAX",
+            Self::Plan => "EXPLAIN PLAN json = 1, indexes = 1, description = 1",
+            Self::Pipeline => "EXPLAIN PIPELINE",
+            Self::Estimate => "EXPLAIN ESTIMATE",
+            Self::QueryTree => "EXPLAIN QUERY TREE",
+        }
+    }
+
+    /// The statement explaining `sql`.
+    pub(crate) fn to_sql(self, sql: &str) -> String {
+        format!("{} {}", self.as_str(), sql.trim().trim_end_matches(';'))
+    }
+}
+
+impl fmt::Display for ExplainKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The output of [`Session::explain`](crate::session::Session::explain).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Explain {
+    /// The root of the query plan, for [`ExplainKind::Plan`].
+    Plan(PlanNode),
+    /// One row per table read, for [`ExplainKind::Estimate`].
+    Estimate(Vec<Estimate>),
+    /// The output lines of the other kinds.
+    Text(Vec<String>),
+}
+
+impl Explain {
+    /// Parse the output of `kind`, as returned in `TabSeparatedRaw`.
+    pub(crate) fn parse(kind: ExplainKind, output: &str) -> Result<Self> {
+        match kind {
+            ExplainKind::Plan => {
+                let json = Json::parse(output)?;
+                let plan = json
+                    .as_array()
+                    .first()
+                    .and_then(|item| item.get("Plan"))
+                    .ok_or_else(|| {
+                        Error::InvalidData("EXPLAIN PLAN returned no plan".to_string())
+                    })?;
+                PlanNode::from_json(plan).map(Self::Plan)
+            }
+            ExplainKind::Estimate => output
+                .lines()
+                .map(Estimate::parse)
+                .collect::<Result<_>>()
+                .map(Self::Estimate),
+            _ => Ok(Self::Text(output.lines().map(str::to_string).collect())),
+        }
+    }
+
+    /// The query plan, if this is the output of [`ExplainKind::Plan`].
+    pub fn plan(&self) -> Option<&PlanNode> {
+        match self {
+            Self::Plan(plan) => Some(plan),
+            _ => None,
+        }
+    }
+
+    /// The estimates, if this is the output of [`ExplainKind::Estimate`].
+    pub fn estimates(&self) -> Option<&[Estimate]> {
+        match self {
+            Self::Estimate(estimates) => Some(estimates),
+            _ => None,
+        }
+    }
+
+    /// The output lines, if this is the output of a textual kind.
+    pub fn lines(&self) -> Option<&[String]> {
+        match self {
+            Self::Text(lines) => Some(lines),
+            _ => None,
+        }
+    }
+}
+
+/// A step of a query plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanNode {
+    /// The step type, e.g. `Expression`, `Filter` or `ReadFromMergeTree`.
+    pub node_type: String,
+    /// The step description, e.g. the table read by `ReadFromMergeTree`.
+    pub description: Option<String>,
+    /// Indexes applied by a reading step.
+    pub indexes: Vec<IndexUsage>,
+    /// The steps feeding this one.
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    fn from_json(json: &Json) -> Result<Self> {
+        let node_type = json
+            .get("Node Type")
+            .and_then(Json::as_str)
+            .ok_or_else(|| Error::InvalidData("Plan step without a node type".to_string()))?;
+        Ok(Self {
+            node_type: node_type.to_string(),
+            description: json
+                .get("Description")
+                .and_then(Json::as_str)
+                .map(str::to_string),
+            indexes: json
+                .get("Indexes")
+                .map_or(&[][..], Json::as_array)
+                .iter()
+                .map(IndexUsage::from_json)
+                .collect(),
+            children: json
+                .get("Plans")
+                .map_or(&[][..], Json::as_array)
+                .iter()
+                .map(Self::from_json)
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    /// This step and all steps below it, depth first.
+    pub fn iter(&self) -> impl Iterator<Item = &PlanNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// The first step of the given type, depth first.
+    pub fn find(&self, node_type: &str) -> Option<&PlanNode> {
+        self.iter().find(|node| node.node_type == node_type)
+    }
+
+    /// All indexes applied anywhere in the plan.
+    pub fn index_usage(&self) -> impl Iterator<Item = &IndexUsage> {
+        self.iter().flat_map(|node| &node.indexes)
+    }
+
+    /// Whether any read is filtered by the primary key.
+    pub fn uses_primary_key(&self) -> bool {
+        self.index_usage()
+            .any(|index| index.kind == IndexUsage::PRIMARY_KEY && index.is_filtering())
+    }
+}
+
+/// An index applied when reading a MergeTree table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexUsage {
+    /// The index type: `MinMax`, `Partition`, `PrimaryKey` or `Skip`.
+    pub kind: String,
+    /// The name of a skip index.
+    pub name: Option<String>,
+    /// The key columns.
+    pub keys: Vec<String>,
+    /// The condition checked against the index; `true` if nothing is filtered.
+    pub condition: Option<String>,
+    /// Parts before applying the index.
+    pub initial_parts: Option<u64>,
+    /// Parts selected by the index.
+    pub selected_parts: Option<u64>,
+    /// Granules before applying the index.
+    pub initial_granules: Option<u64>,
+    /// Granules selected by the index.
+    pub selected_granules: Option<u64>,
+}
+
+impl IndexUsage {
+    /// The [`kind`](Self::kind) of the primary key index.
+    pub const PRIMARY_KEY: &'static str = "PrimaryKey";
+
+    fn from_json(json: &Json) -> Self {
+        let string = |key| json.get(key).and_then(Json::as_str).map(str::to_string);
+        let number = |key| json.get(key).and_then(Json::as_u64);
+        Self {
+            kind: string("Type").unwrap_or_default(),
+            name: string("Name"),
+            keys: json
+                .get("Keys")
+                .map_or(&[][..], Json::as_array)
+                .iter()
+                .filter_map(|key| key.as_str().map(str::to_string))
+                .collect(),
+            condition: string("Condition"),
+            initial_parts: number("Initial Parts"),
+            selected_parts: number("Selected Parts"),
+            initial_granules: number("Initial Granules"),
+            selected_granules: number("Selected Granules"),
+        }
+    }
+
+    /// Whether the index has a condition to filter by.
+    pub fn is_filtering(&self) -> bool {
+        self.condition.as_deref().is_some_and(|c| c != "true")
+    }
+}
+
+/// One row of `EXPLAIN ESTIMATE`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Estimate {
+    /// The database of the table.
+    pub database: String,
+    /// The table to be read.
+    pub table: String,
+    /// Parts to be read.
+    pub parts: u64,
+    /// Rows to be read.
+    pub rows: u64,
+    /// Marks to be read.
+    pub marks: u64,
+}
+
+impl Estimate {
+    fn parse(line: &str) -> Result<Self> {
+        let invalid = || Error::InvalidData(format!("Invalid EXPLAIN ESTIMATE row '{line}'"));
+        let mut fields = line.split('\t');
+        let mut next = || fields.next().ok_or_else(invalid);
+        let database = next()?.to_string();
+        let table = next()?.to_string();
+        let mut number = || next()?.parse::<u64>().map_err(|_| invalid());
+        Ok(Self {
+            database,
+            table,
+            parts: number()?,
+            rows: number()?,
+            marks: number()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAN: &str = r#"[
+  {
+    "Plan": {
+      "Node Type": "Expression",
+      "Description": "(Project names + Projection)",
+      "Plans": [
+        {
+          "Node Type": "ReadFromMergeTree",
+          "Description": "default.events",
+          "Indexes": [
+            {
+              "Type": "PrimaryKey",
+              "Keys": ["id"],
+              "Condition": "(id in [42, 42])",
+              "Initial Parts": 3,
+              "Selected Parts": 1,
+              "Initial Granules": 30,
+              "Selected Granules": 1
+            }
+          ]
+        }
+      ]
+    }
+  }
+]"#;
+
+    #[test]
+    fn test_parse_plan() {
+        let explain = Explain::parse(ExplainKind::Plan, PLAN).unwrap();
+        let plan = explain.plan().unwrap();
+        assert_eq!(plan.node_type, "Expression");
+        let read = plan.find("ReadFromMergeTree").unwrap();
+        assert_eq!(read.description.as_deref(), Some("default.events"));
+        assert_eq!(read.indexes[0].keys, ["id"]);
+        assert_eq!(read.indexes[0].selected_granules, Some(1));
+        assert!(plan.uses_primary_key());
+
+        let unfiltered = PLAN.replace("(id in [42, 42])", "true");
+        let explain = Explain::parse(ExplainKind::Plan, &unfiltered).unwrap();
+        assert!(!explain.plan().unwrap().uses_primary_key());
+    }
+
+    #[test]
+    fn test_parse_estimate() {
+        let explain =
+            Explain::parse(ExplainKind::Estimate, "default\tevents\t1\t8192\t1\n").unwrap();
+        assert_eq!(
+            explain.estimates().unwrap(),
+            [Estimate {
+                database: "default".to_string(),
+                table: "events".to_string(),
+                parts: 1,
+                rows: 8192,
+                marks: 1,
+            }]
+        );
+        assert!(Explain::parse(ExplainKind::Estimate, "default\tevents\tx").is_err());
+    }
+
+    #[test]
+    fn test_to_sql() {
+        assert_eq!(
+            ExplainKind::Plan.to_sql(" SELECT 1; "),
+            "EXPLAIN PLAN json = 1, indexes = 1, description = 1 SELECT 1"
+        );
+        assert_eq!(
+            ExplainKind::QueryTree.to_sql("SELECT 1"),
+            "EXPLAIN QUERY TREE SELECT 1"
+        );
+    }
+}
@@ -3,301 +3,1394 @@
 //! This module defines the various data formats supported by chDB for reading
 //! input data and formatting query results.
 
-/// Input formats for reading data into chDB.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::Error;
+use crate::query_builder::{append_settings, check_setting_name, Value};
+
+/// Define a format enum whose variant names are also the names chDB expects.
+macro_rules! define_formats {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($variant:ident),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl $name {
+            /// Every variant, in declaration order.
+            pub const ALL: &'static [Self] = &[$(Self::$variant),*];
+
+            /// Get the string representation of the format.
+            ///
+            /// This returns the format name as it should be used in SQL queries
+            /// (e.g. in `file()` calls or `FORMAT` clauses) and when executing queries.
+            ///
+            /// # Returns
+            ///
+            /// Returns the format name as a static string slice.
+            pub const fn as_str(self) -> &'static str {
+                match self {
+                    $(Self::$variant => stringify!($variant)),*
+                }
+            }
+
+            /// Iterate over every format.
+            pub fn all() -> impl Iterator<Item = Self> {
+                Self::ALL.iter().copied()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            /// Parse a format name, ignoring ASCII case and accepting the
+            /// aliases chDB accepts (e.g. `TSV`, `NDJSON`).
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let name = resolve_alias(s);
+                Self::all()
+                    .find(|f| f.as_str().eq_ignore_ascii_case(name))
+                    .ok_or_else(|| Error::UnknownFormat(s.to_string()))
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+                name.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+define_formats! {
+    /// Input formats for reading data into chDB.
+    ///
+    /// These formats specify how data should be parsed when reading from files or
+    /// other sources. See the [ClickHouse documentation](https://clickhouse.com/docs/en/interfaces/formats/)
+    /// for details on each format.
+    pub enum InputFormat {
+        TabSeparated,
+        TabSeparatedRaw,
+        TabSeparatedWithNames,
+        TabSeparatedWithNamesAndTypes,
+        TabSeparatedRawWithNames,
+        TabSeparatedRawWithNamesAndTypes,
+        Template,
+        TemplateIgnoreSpaces,
+        CSV,
+        CSVWithNames,
+        CSVWithNamesAndTypes,
+        CustomSeparated,
+        CustomSeparatedWithNames,
+        CustomSeparatedWithNamesAndTypes,
+        Values,
+        JSON,
+        JSONAsString,
+        JSONAsObject,
+        JSONStrings,
+        JSONColumns,
+        JSONColumnsWithMetadata,
+        JSONCompact,
+        JSONCompactColumns,
+        JSONEachRow,
+        JSONStringsEachRow,
+        JSONCompactEachRow,
+        JSONCompactEachRowWithNames,
+        JSONCompactEachRowWithNamesAndTypes,
+        JSONCompactStringsEachRow,
+        JSONCompactStringsEachRowWithNames,
+        JSONCompactStringsEachRowWithNamesAndTypes,
+        JSONObjectEachRow,
+        BSONEachRow,
+        TSKV,
+        Protobuf,
+        ProtobufSingle,
+        ProtobufList,
+        Avro,
+        AvroConfluent,
+        Parquet,
+        ParquetMetadata,
+        ORC,
+        One,
+        Npy,
+        RowBinary,
+        RowBinaryWithNames,
+        RowBinaryWithNamesAndTypes,
+        RowBinaryWithDefaults,
+        Native,
+        CapnProto,
+        LineAsString,
+        Regexp,
+        RawBLOB,
+        MsgPack,
+        MySQLDump,
+        DWARF,
+        Form,
+    }
+}
+
+define_formats! {
+    /// Output formats for query results.
+    ///
+    /// These formats specify how query results should be formatted when returned.
+    /// See the [ClickHouse documentation](https://clickhouse.com/docs/en/interfaces/formats/)
+    /// for details on each format.
+    pub enum OutputFormat {
+        TabSeparated,
+        TabSeparatedRaw,
+        TabSeparatedWithNames,
+        TabSeparatedWithNamesAndTypes,
+        TabSeparatedRawWithNames,
+        TabSeparatedRawWithNamesAndTypes,
+        Template,
+        CSV,
+        CSVWithNames,
+        CSVWithNamesAndTypes,
+        CustomSeparated,
+        CustomSeparatedWithNames,
+        CustomSeparatedWithNamesAndTypes,
+        Values,
+        JSON,
+        JSONStrings,
+        JSONColumns,
+        JSONColumnsWithMetadata,
+        JSONCompact,
+        JSONCompactStrings,
+        JSONCompactColumns,
+        JSONEachRow,
+        PrettyJSONEachRow,
+        JSONEachRowWithProgress,
+        JSONStringsEachRow,
+        JSONStringsEachRowWithProgress,
+        JSONCompactEachRow,
+        JSONCompactEachRowWithNames,
+        JSONCompactEachRowWithNamesAndTypes,
+        JSONCompactStringsEachRow,
+        JSONCompactStringsEachRowWithNames,
+        JSONCompactStringsEachRowWithNamesAndTypes,
+        JSONObjectEachRow,
+        BSONEachRow,
+        TSKV,
+        Pretty,
+        PrettyNoEscapes,
+        PrettyMonoBlock,
+        PrettyNoEscapesMonoBlock,
+        PrettyCompact,
+        PrettyCompactNoEscapes,
+        PrettyCompactMonoBlock,
+        PrettyCompactNoEscapesMonoBlock,
+        PrettySpace,
+        PrettySpaceNoEscapes,
+        PrettySpaceMonoBlock,
+        PrettySpaceNoEscapesMonoBlock,
+        Prometheus,
+        Protobuf,
+        ProtobufSingle,
+        ProtobufList,
+        Avro,
+        Parquet,
+        ORC,
+        Npy,
+        RowBinary,
+        RowBinaryWithNames,
+        RowBinaryWithNamesAndTypes,
+        Native,
+        Null,
+        XML,
+        CapnProto,
+        LineAsString,
+        RawBLOB,
+        MsgPack,
+        Markdown,
+        Vertical,
+    }
+}
+
+/// Resolve the format aliases chDB accepts to their canonical names.
+fn resolve_alias(name: &str) -> &str {
+    const ALIASES: &[(&str, &str)] = &[
+        ("TSV", "TabSeparated"),
+        ("TSVRaw", "TabSeparatedRaw"),
+        ("Raw", "TabSeparatedRaw"),
+        ("TSVWithNames", "TabSeparatedWithNames"),
+        ("TSVWithNamesAndTypes", "TabSeparatedWithNamesAndTypes"),
+        ("TSVRawWithNames", "TabSeparatedRawWithNames"),
+        (
+            "TSVRawWithNamesAndTypes",
+            "TabSeparatedRawWithNamesAndTypes",
+        ),
+        ("JSONLines", "JSONEachRow"),
+        ("NDJSON", "JSONEachRow"),
+        ("PrettyJSONLines", "PrettyJSONEachRow"),
+        ("PrettyNDJSON", "PrettyJSONEachRow"),
+    ];
+    ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+        .map_or(name, |(_, canonical)| canonical)
+}
+
+/// Format names registered in libchdb, as reported by `system.formats`,
+/// with whether each one can be used for input and for output.
 ///
-/// These formats specify how data should be parsed when reading from files or
-/// other sources. See the [ClickHouse documentation](https://clickhouse.com/docs/en/interfaces/formats/)
-/// for details on each format.
-#[derive(Debug, Clone, Copy)]
-pub enum InputFormat {
-    TabSeparated,
-    TabSeparatedRaw,
-    TabSeparatedWithNames,
-    TabSeparatedWithNamesAndTypes,
-    TabSeparatedRawWithNames,
-    TabSeparatedRawWithNamesAndTypes,
-    Template,
-    TemplateIgnoreSpaces,
-    CSV,
-    CSVWithNames,
-    CSVWithNamesAndTypes,
-    CustomSeparated,
-    CustomSeparatedWithNames,
-    CustomSeparatedWithNamesAndTypes,
-    Values,
-    JSON,
-    JSONAsString,
-    JSONAsObject,
-    JSONStrings,
-    JSONColumns,
-    JSONColumnsWithMetadata,
-    JSONCompact,
-    JSONCompactColumns,
-    JSONEachRow,
-    JSONStringsEachRow,
-    JSONCompactEachRow,
-    JSONCompactEachRowWithNames,
-    JSONCompactEachRowWithNamesAndTypes,
-    JSONCompactStringsEachRow,
-    JSONCompactStringsEachRowWithNames,
-    JSONCompactStringsEachRowWithNamesAndTypes,
-    JSONObjectEachRow,
-    BSONEachRow,
-    TSKV,
-    Protobuf,
-    ProtobufSingle,
-    ProtobufList,
-    Avro,
-    AvroConfluent,
-    Parquet,
-    ParquetMetadata,
-    ORC,
-    One,
-    Npy,
-    RowBinary,
-    RowBinaryWithNames,
-    RowBinaryWithNamesAndTypes,
-    RowBinaryWithDefaults,
-    Native,
-    CapnProto,
-    LineAsString,
-    Regexp,
-    RawBLOB,
-    MsgPack,
-    MySQLDump,
-    DWARF,
-    Form,
+/// Every [`InputFormat`] and [`OutputFormat`] variant is checked against this
+/// table at compile time, and against the loaded library by `tests/formats.rs`.
+pub(crate) const CHDB_FORMATS: &[(&str, bool, bool)] = &[
+    ("TabSeparated", true, true),
+    ("TabSeparatedRaw", true, true),
+    ("TabSeparatedWithNames", true, true),
+    ("TabSeparatedWithNamesAndTypes", true, true),
+    ("TabSeparatedRawWithNames", true, true),
+    ("TabSeparatedRawWithNamesAndTypes", true, true),
+    ("Template", true, true),
+    ("TemplateIgnoreSpaces", true, false),
+    ("CSV", true, true),
+    ("CSVWithNames", true, true),
+    ("CSVWithNamesAndTypes", true, true),
+    ("CustomSeparated", true, true),
+    ("CustomSeparatedWithNames", true, true),
+    ("CustomSeparatedWithNamesAndTypes", true, true),
+    ("SQLInsert", false, true),
+    ("Values", true, true),
+    ("Vertical", false, true),
+    ("JSON", true, true),
+    ("JSONAsString", true, false),
+    ("JSONAsObject", true, false),
+    ("JSONStrings", true, true),
+    ("JSONColumns", true, true),
+    ("JSONColumnsWithMetadata", true, true),
+    ("JSONCompact", true, true),
+    ("JSONCompactStrings", false, true),
+    ("JSONCompactColumns", true, true),
+    ("JSONEachRow", true, true),
+    ("PrettyJSONEachRow", false, true),
+    ("JSONEachRowWithProgress", false, true),
+    ("JSONStringsEachRow", true, true),
+    ("JSONStringsEachRowWithProgress", false, true),
+    ("JSONCompactEachRow", true, true),
+    ("JSONCompactEachRowWithNames", true, true),
+    ("JSONCompactEachRowWithNamesAndTypes", true, true),
+    ("JSONCompactStringsEachRow", true, true),
+    ("JSONCompactStringsEachRowWithNames", true, true),
+    ("JSONCompactStringsEachRowWithNamesAndTypes", true, true),
+    ("JSONObjectEachRow", true, true),
+    ("BSONEachRow", true, true),
+    ("TSKV", true, true),
+    ("Pretty", false, true),
+    ("PrettyNoEscapes", false, true),
+    ("PrettyMonoBlock", false, true),
+    ("PrettyNoEscapesMonoBlock", false, true),
+    ("PrettyCompact", false, true),
+    ("PrettyCompactNoEscapes", false, true),
+    ("PrettyCompactMonoBlock", false, true),
+    ("PrettyCompactNoEscapesMonoBlock", false, true),
+    ("PrettySpace", false, true),
+    ("PrettySpaceNoEscapes", false, true),
+    ("PrettySpaceMonoBlock", false, true),
+    ("PrettySpaceNoEscapesMonoBlock", false, true),
+    ("Prometheus", false, true),
+    ("Protobuf", true, true),
+    ("ProtobufSingle", true, true),
+    ("ProtobufList", true, true),
+    ("Avro", true, true),
+    ("AvroConfluent", true, false),
+    ("Parquet", true, true),
+    ("ParquetMetadata", true, false),
+    ("Arrow", true, true),
+    ("ArrowStream", true, true),
+    ("ORC", true, true),
+    ("One", true, false),
+    ("Npy", true, true),
+    ("RowBinary", true, true),
+    ("RowBinaryWithNames", true, true),
+    ("RowBinaryWithNamesAndTypes", true, true),
+    ("RowBinaryWithDefaults", true, false),
+    ("Native", true, true),
+    ("Null", false, true),
+    ("XML", false, true),
+    ("CapnProto", true, true),
+    ("LineAsString", true, true),
+    ("Regexp", true, false),
+    ("RawBLOB", true, true),
+    ("MsgPack", true, true),
+    ("MySQLDump", true, false),
+    ("DWARF", true, false),
+    ("Markdown", false, true),
+    ("Form", true, false),
+];
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
 }
 
-/// Output formats for query results.
+const fn chdb_supports(name: &str, input: bool) -> bool {
+    let mut i = 0;
+    while i < CHDB_FORMATS.len() {
+        let (known, is_input, is_output) = CHDB_FORMATS[i];
+        if str_eq(known, name) {
+            return if input { is_input } else { is_output };
+        }
+        i += 1;
+    }
+    false
+}
+
+// Fail the build if a variant does not name a format chDB accepts in that direction.
+const _: () = {
+    let mut i = 0;
+    while i < InputFormat::ALL.len() {
+        assert!(
+            chdb_supports(InputFormat::ALL[i].as_str(), true),
+            "InputFormat variant is not a chDB input format"
+        );
+        i += 1;
+    }
+    let mut i = 0;
+    while i < OutputFormat::ALL.len() {
+        assert!(
+            chdb_supports(OutputFormat::ALL[i].as_str(), false),
+            "OutputFormat variant is not a chDB output format"
+        );
+        i += 1;
+    }
+};
+
+/// Compression methods chDB can apply to output files and read from input files.
 ///
-/// These formats specify how query results should be formatted when returned.
-/// See the [ClickHouse documentation](https://clickhouse.com/docs/en/interfaces/formats/)
-/// for details on each format.
-#[derive(Debug, Clone, Copy)]
-pub enum OutputFormat {
-    TabSeparated,
-    TabSeparatedRaw,
-    TabSeparatedWithNames,
-    TabSeparatedWithNamesAndTypes,
-    TabSeparatedRawWithNames,
-    TabSeparatedRawWithNamesAndTypes,
-    Template,
-    CSV,
-    CSVWithNames,
-    CSVWithNamesAndTypes,
-    CustomSeparated,
-    CustomSeparatedWithNames,
-    CustomSeparatedWithNamesAndTypes,
-    Values,
-    JSON,
-    JSONStrings,
-    JSONColumns,
-    JSONColumnsWithMetadata,
-    JSONCompact,
-    JSONCompactStrings,
-    JSONCompactColumns,
-    JSONEachRow,
-    PrettyJSONEachRow,
-    JSONEachRowWithProgress,
-    JSONStringsEachRow,
-    JSONStringsEachRowWithProgress,
-    JSONCompactEachRow,
-    JSONCompactEachRowWithNames,
-    JSONCompactEachRowWithNamesAndTypes,
-    JSONCompactStringsEachRow,
-    JSONCompactStringsEachRowWithNames,
-    JSONCompactStringsEachRowWithNamesAndTypes,
-    JSONObjectEachRow,
-    BSONEachRow,
-    TSKV,
-    Pretty,
-    PrettyNoEscapes,
-    PrettyMonoBlock,
-    PrettyNoEscapesMonoBlock,
-    PrettyCompact,
-    PrettyCompactNoEscapes,
-    PrettyCompactMonoBlock,
-    PrettyCompactNoEscapesMonoBlock,
-    PrettySpace,
-    PrettySpaceNoEscapes,
-    PrettySpaceMonoBlock,
-    PrettySpaceNoEscapesMonoBlock,
-    Prometheus,
-    Protobuf,
-    ProtobufSingle,
-    ProtobufList,
-    Avro,
-    Parquet,
-    ORC,
-    Npy,
-    RowBinary,
-    RowBinaryWithNames,
-    RowBinaryWithNamesAndTypes,
-    Native,
-    Null,
-    XML,
-    CapnProto,
-    LineAsString,
-    RawBLOB,
-    MsgPack,
-    Markdown,
-    Vertical,
+/// See the [ClickHouse documentation](https://clickhouse.com/docs/en/sql-reference/statements/select/into-outfile)
+/// for the supported methods.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Deflate,
+    Brotli,
+    Xz,
+    Zstd,
+    Lz4,
+    Bzip2,
+}
+
+impl Compression {
+    /// Get the compression method name as accepted by ClickHouse.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Deflate => "deflate",
+            Self::Brotli => "br",
+            Self::Xz => "xz",
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+            Self::Bzip2 => "bz2",
+        }
+    }
+
+    /// The conventional file extension, without the leading dot.
+    pub const fn file_extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Deflate => "deflate",
+            Self::Brotli => "br",
+            Self::Xz => "xz",
+            Self::Zstd => "zst",
+            Self::Lz4 => "lz4",
+            Self::Bzip2 => "bz2",
+        }
+    }
+
+    /// Infer the compression method from a file extension such as `gz` or `zst`.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        Some(match ext.to_ascii_lowercase().as_str() {
+            "gz" | "gzip" => Self::Gzip,
+            "deflate" => Self::Deflate,
+            "br" => Self::Brotli,
+            "xz" | "lzma" => Self::Xz,
+            "zst" | "zstd" => Self::Zstd,
+            "lz4" => Self::Lz4,
+            "bz2" => Self::Bzip2,
+            _ => return None,
+        })
+    }
+}
+
+/// Split a path into its format extension and optional compression method.
+///
+/// `logs/a.csv.gz` yields `("csv", Some(Compression::Gzip))`.
+pub(crate) fn split_extension(path: &str) -> Option<(&str, Option<Compression>)> {
+    let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let mut parts = name.rsplit('.');
+    let mut ext = parts.next()?;
+    let compression = Compression::from_extension(ext);
+    if compression.is_some() {
+        ext = parts.next()?;
+    }
+    // A name without any dot yields itself as the "extension".
+    if ext == name {
+        return None;
+    }
+    Some((ext, compression))
 }
 
 impl InputFormat {
-    /// Get the string representation of the input format.
+    /// Infer the input format from a file extension such as `csv` or `parquet`.
     ///
-    /// This returns the format name as it should be used in SQL queries
-    /// (e.g., in `file()` function calls).
-    ///
-    /// # Returns
+    /// The match is case-insensitive. Returns `None` for unknown extensions.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        Some(match ext.to_ascii_lowercase().as_str() {
+            "csv" => Self::CSV,
+            "tsv" | "tab" => Self::TabSeparated,
+            "json" | "jsonl" | "ndjson" => Self::JSONEachRow,
+            "parquet" => Self::Parquet,
+            "orc" => Self::ORC,
+            "avro" => Self::Avro,
+            "npy" => Self::Npy,
+            "msgpack" => Self::MsgPack,
+            "bson" => Self::BSONEachRow,
+            "capnp" => Self::CapnProto,
+            "native" => Self::Native,
+            _ => return None,
+        })
+    }
+
+    /// Infer the input format from a file path or glob pattern.
     ///
-    /// Returns the format name as a static string slice.
-    pub const fn as_str(self) -> &'static str {
+    /// Compression suffixes such as `.gz` or `.zst` are skipped, so
+    /// `logs/*.csv.gz` is recognized as [`InputFormat::CSV`].
+    pub fn from_path(path: &str) -> Option<Self> {
+        Self::from_extension(split_extension(path)?.0)
+    }
+
+    /// Whether the format is binary rather than human-readable text.
+    pub const fn is_binary(self) -> bool {
+        matches!(
+            self,
+            Self::BSONEachRow
+                | Self::Protobuf
+                | Self::ProtobufSingle
+                | Self::ProtobufList
+                | Self::Avro
+                | Self::AvroConfluent
+                | Self::Parquet
+                | Self::ParquetMetadata
+                | Self::ORC
+                | Self::Npy
+                | Self::RowBinary
+                | Self::RowBinaryWithNames
+                | Self::RowBinaryWithNamesAndTypes
+                | Self::RowBinaryWithDefaults
+                | Self::Native
+                | Self::CapnProto
+                | Self::RawBLOB
+                | Self::MsgPack
+                | Self::DWARF
+        )
+    }
+
+    /// Whether the data starts with a header carrying column names.
+    pub const fn has_header(self) -> bool {
+        matches!(
+            self,
+            Self::TabSeparatedWithNames
+                | Self::TabSeparatedWithNamesAndTypes
+                | Self::TabSeparatedRawWithNames
+                | Self::TabSeparatedRawWithNamesAndTypes
+                | Self::CSVWithNames
+                | Self::CSVWithNamesAndTypes
+                | Self::CustomSeparatedWithNames
+                | Self::CustomSeparatedWithNamesAndTypes
+                | Self::JSON
+                | Self::JSONStrings
+                | Self::JSONColumnsWithMetadata
+                | Self::JSONCompact
+                | Self::JSONCompactEachRowWithNames
+                | Self::JSONCompactEachRowWithNamesAndTypes
+                | Self::JSONCompactStringsEachRowWithNames
+                | Self::JSONCompactStringsEachRowWithNamesAndTypes
+                | Self::RowBinaryWithNames
+                | Self::RowBinaryWithNamesAndTypes
+        )
+    }
+
+    /// Whether the data carries column types, so no structure has to be given.
+    pub const fn has_types(self) -> bool {
+        matches!(
+            self,
+            Self::TabSeparatedWithNamesAndTypes
+                | Self::TabSeparatedRawWithNamesAndTypes
+                | Self::CSVWithNamesAndTypes
+                | Self::CustomSeparatedWithNamesAndTypes
+                | Self::JSON
+                | Self::JSONStrings
+                | Self::JSONColumnsWithMetadata
+                | Self::JSONCompact
+                | Self::JSONCompactEachRowWithNamesAndTypes
+                | Self::JSONCompactStringsEachRowWithNamesAndTypes
+                | Self::RowBinaryWithNamesAndTypes
+                | Self::Avro
+                | Self::AvroConfluent
+                | Self::Parquet
+                | Self::ORC
+                | Self::Native
+        )
+    }
+
+    /// Whether the data can be parsed incrementally, without reading it whole.
+    pub const fn is_streamable(self) -> bool {
+        !matches!(
+            self,
+            Self::JSON
+                | Self::JSONStrings
+                | Self::JSONColumns
+                | Self::JSONColumnsWithMetadata
+                | Self::JSONCompact
+                | Self::JSONCompactColumns
+                | Self::JSONObjectEachRow
+                | Self::ProtobufList
+                | Self::Parquet
+                | Self::ParquetMetadata
+                | Self::ORC
+                | Self::Npy
+                | Self::DWARF
+        )
+    }
+
+    /// The MIME type of data in this format.
+    pub const fn mime_type(self) -> &'static str {
         match self {
-            Self::TabSeparated => "TabSeparated",
-            Self::TabSeparatedRaw => "TabSeparatedRaw",
-            Self::TabSeparatedWithNames => "TabSeparatedWithNames",
-            Self::TabSeparatedWithNamesAndTypes => "TabSeparatedWithNamesAndTypes",
-            Self::TabSeparatedRawWithNames => "TabSeparatedRawWithNames",
-            Self::TabSeparatedRawWithNamesAndTypes => "TabSeparatedRawWithNamesAndTypes",
-            Self::Template => "Template",
-            Self::TemplateIgnoreSpaces => "TemplateIgnoreSpaces",
-            Self::CSV => "CSV",
-            Self::CSVWithNames => "CSVWithNames",
-            Self::CSVWithNamesAndTypes => "CSVWithNamesAndTypes",
-            Self::CustomSeparated => "CustomSeparated",
-            Self::CustomSeparatedWithNames => "CustomSeparatedWithNames",
-            Self::CustomSeparatedWithNamesAndTypes => "CustomSeparatedWithNamesAndTypes",
-            Self::Values => "Values",
-            Self::JSON => "JSON",
-            Self::JSONAsString => "JSONAsString",
-            Self::JSONAsObject => "JSONAsObject",
-            Self::JSONStrings => "JSONStrings",
-            Self::JSONColumns => "JSONColumns",
-            Self::JSONColumnsWithMetadata => "JSONColumnsWithMetadata",
-            Self::JSONCompact => "JSONCompact",
-            Self::JSONCompactColumns => "JSONCompactColumns",
-            Self::JSONEachRow => "JSONEachRow",
-            Self::JSONStringsEachRow => "JSONStringsEachRow",
-            Self::JSONCompactEachRow => "JSONCompactEachRow",
-            Self::JSONCompactEachRowWithNames => "JSONCompactEachRowWithNames",
-            Self::JSONCompactEachRowWithNamesAndTypes => "JSONCompactEachRowWithNamesAndTypes",
-            Self::JSONCompactStringsEachRow => "JSONCompactStringsEachRow",
-            Self::JSONCompactStringsEachRowWithNames => "JSONCompactStringsEachRowWithNames",
-            Self::JSONCompactStringsEachRowWithNamesAndTypes => {
-                "JSONCompactStringsEachRowWithNamesAndTypes"
-            }
-            Self::JSONObjectEachRow => "JSONObjectEachRow",
-            Self::BSONEachRow => "BSONEachRow",
-            Self::TSKV => "TSKV",
-            Self::Protobuf => "Protobuf",
-            Self::ProtobufSingle => "ProtobufSingle",
-            Self::ProtobufList => "ProtobufList",
-            Self::Avro => "Avro",
-            Self::AvroConfluent => "AvroConfluent",
-            Self::Parquet => "Parquet",
-            Self::ParquetMetadata => "ParquetMetadata",
-            Self::ORC => "ORC",
-            Self::One => "One",
-            Self::Npy => "Npy",
-            Self::RowBinary => "RowBinary",
-            Self::RowBinaryWithNames => "RowBinaryWithNames",
-            Self::RowBinaryWithNamesAndTypes => "RowBinaryWithNamesAndTypes",
-            Self::RowBinaryWithDefaults => "RowBinaryWithDefaults",
-            Self::Native => "Native",
-            Self::CapnProto => "CapnProto",
-            Self::LineAsString => "LineAsString",
-            Self::Regexp => "Regexp",
-            Self::RawBLOB => "RawBLOB",
-            Self::MsgPack => "MsgPack",
-            Self::MySQLDump => "MySQLDump",
-            Self::DWARF => "DWARF",
-            Self::Form => "Form",
+            Self::TabSeparated
+            | Self::TabSeparatedRaw
+            | Self::TabSeparatedWithNames
+            | Self::TabSeparatedWithNamesAndTypes
+            | Self::TabSeparatedRawWithNames
+            | Self::TabSeparatedRawWithNamesAndTypes => "text/tab-separated-values",
+            Self::CSV | Self::CSVWithNames | Self::CSVWithNamesAndTypes => "text/csv",
+            Self::JSON
+            | Self::JSONAsString
+            | Self::JSONAsObject
+            | Self::JSONStrings
+            | Self::JSONColumns
+            | Self::JSONColumnsWithMetadata
+            | Self::JSONCompact
+            | Self::JSONCompactColumns
+            | Self::JSONObjectEachRow => "application/json",
+            Self::JSONEachRow
+            | Self::JSONStringsEachRow
+            | Self::JSONCompactEachRow
+            | Self::JSONCompactEachRowWithNames
+            | Self::JSONCompactEachRowWithNamesAndTypes
+            | Self::JSONCompactStringsEachRow
+            | Self::JSONCompactStringsEachRowWithNames
+            | Self::JSONCompactStringsEachRowWithNamesAndTypes => "application/x-ndjson",
+            Self::Parquet => "application/vnd.apache.parquet",
+            Self::Avro | Self::AvroConfluent => "application/avro",
+            Self::Protobuf | Self::ProtobufSingle | Self::ProtobufList => "application/x-protobuf",
+            Self::MsgPack => "application/x-msgpack",
+            Self::BSONEachRow => "application/bson",
+            Self::MySQLDump => "application/sql",
+            Self::Form => "application/x-www-form-urlencoded",
+            Self::Template
+            | Self::TemplateIgnoreSpaces
+            | Self::CustomSeparated
+            | Self::CustomSeparatedWithNames
+            | Self::CustomSeparatedWithNamesAndTypes
+            | Self::Values
+            | Self::TSKV
+            | Self::One
+            | Self::LineAsString
+            | Self::Regexp => "text/plain",
+            Self::ParquetMetadata
+            | Self::ORC
+            | Self::Npy
+            | Self::RowBinary
+            | Self::RowBinaryWithNames
+            | Self::RowBinaryWithNamesAndTypes
+            | Self::RowBinaryWithDefaults
+            | Self::Native
+            | Self::CapnProto
+            | Self::RawBLOB
+            | Self::DWARF => "application/octet-stream",
+        }
+    }
+
+    /// The conventional file extension, without the leading dot.
+    pub const fn file_extension(self) -> &'static str {
+        match self {
+            Self::TabSeparated
+            | Self::TabSeparatedRaw
+            | Self::TabSeparatedWithNames
+            | Self::TabSeparatedWithNamesAndTypes
+            | Self::TabSeparatedRawWithNames
+            | Self::TabSeparatedRawWithNamesAndTypes => "tsv",
+            Self::CSV | Self::CSVWithNames | Self::CSVWithNamesAndTypes => "csv",
+            Self::JSON
+            | Self::JSONAsString
+            | Self::JSONAsObject
+            | Self::JSONStrings
+            | Self::JSONColumns
+            | Self::JSONColumnsWithMetadata
+            | Self::JSONCompact
+            | Self::JSONCompactColumns
+            | Self::JSONObjectEachRow => "json",
+            Self::JSONEachRow
+            | Self::JSONStringsEachRow
+            | Self::JSONCompactEachRow
+            | Self::JSONCompactEachRowWithNames
+            | Self::JSONCompactEachRowWithNamesAndTypes
+            | Self::JSONCompactStringsEachRow
+            | Self::JSONCompactStringsEachRowWithNames
+            | Self::JSONCompactStringsEachRowWithNamesAndTypes => "ndjson",
+            Self::Parquet | Self::ParquetMetadata => "parquet",
+            Self::ORC => "orc",
+            Self::Avro | Self::AvroConfluent => "avro",
+            Self::Npy => "npy",
+            Self::Protobuf | Self::ProtobufSingle | Self::ProtobufList => "pb",
+            Self::CapnProto => "capnp",
+            Self::MsgPack => "msgpack",
+            Self::BSONEachRow => "bson",
+            Self::Native => "native",
+            Self::MySQLDump => "sql",
+            Self::DWARF => "elf",
+            Self::RowBinary
+            | Self::RowBinaryWithNames
+            | Self::RowBinaryWithNamesAndTypes
+            | Self::RowBinaryWithDefaults
+            | Self::RawBLOB => "bin",
+            Self::Template
+            | Self::TemplateIgnoreSpaces
+            | Self::CustomSeparated
+            | Self::CustomSeparatedWithNames
+            | Self::CustomSeparatedWithNamesAndTypes
+            | Self::Values
+            | Self::TSKV
+            | Self::One
+            | Self::LineAsString
+            | Self::Regexp
+            | Self::Form => "txt",
         }
     }
 }
 
 impl OutputFormat {
-    /// Get the string representation of the output format.
+    /// Infer the output format from a file extension such as `csv` or `parquet`.
     ///
-    /// This returns the format name as it should be used when executing queries.
+    /// The match is case-insensitive. Returns `None` for unknown extensions.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        Some(match ext.to_ascii_lowercase().as_str() {
+            "csv" => Self::CSV,
+            "tsv" | "tab" => Self::TabSeparated,
+            "json" | "jsonl" | "ndjson" => Self::JSONEachRow,
+            "parquet" => Self::Parquet,
+            "orc" => Self::ORC,
+            "avro" => Self::Avro,
+            "npy" => Self::Npy,
+            "msgpack" => Self::MsgPack,
+            "bson" => Self::BSONEachRow,
+            "capnp" => Self::CapnProto,
+            "native" => Self::Native,
+            "xml" => Self::XML,
+            "md" => Self::Markdown,
+            "txt" => Self::TabSeparatedRaw,
+            _ => return None,
+        })
+    }
+
+    /// Infer the output format from a file path.
     ///
-    /// # Returns
+    /// Compression suffixes such as `.gz` or `.zst` are skipped, so
+    /// `export.csv.gz` is recognized as [`OutputFormat::CSV`].
+    pub fn from_path(path: &str) -> Option<Self> {
+        Self::from_extension(split_extension(path)?.0)
+    }
+
+    /// Whether the format is binary rather than human-readable text.
+    pub const fn is_binary(self) -> bool {
+        matches!(
+            self,
+            Self::BSONEachRow
+                | Self::Protobuf
+                | Self::ProtobufSingle
+                | Self::ProtobufList
+                | Self::Avro
+                | Self::Parquet
+                | Self::ORC
+                | Self::Npy
+                | Self::RowBinary
+                | Self::RowBinaryWithNames
+                | Self::RowBinaryWithNamesAndTypes
+                | Self::Native
+                | Self::CapnProto
+                | Self::RawBLOB
+                | Self::MsgPack
+        )
+    }
+
+    /// Whether the output starts with a header carrying column names.
+    pub const fn has_header(self) -> bool {
+        matches!(
+            self,
+            Self::TabSeparatedWithNames
+                | Self::TabSeparatedWithNamesAndTypes
+                | Self::TabSeparatedRawWithNames
+                | Self::TabSeparatedRawWithNamesAndTypes
+                | Self::CSVWithNames
+                | Self::CSVWithNamesAndTypes
+                | Self::CustomSeparatedWithNames
+                | Self::CustomSeparatedWithNamesAndTypes
+                | Self::JSON
+                | Self::JSONStrings
+                | Self::JSONColumnsWithMetadata
+                | Self::JSONCompact
+                | Self::JSONCompactStrings
+                | Self::JSONCompactEachRowWithNames
+                | Self::JSONCompactEachRowWithNamesAndTypes
+                | Self::JSONCompactStringsEachRowWithNames
+                | Self::JSONCompactStringsEachRowWithNamesAndTypes
+                | Self::Pretty
+                | Self::PrettyNoEscapes
+                | Self::PrettyMonoBlock
+                | Self::PrettyNoEscapesMonoBlock
+                | Self::PrettyCompact
+                | Self::PrettyCompactNoEscapes
+                | Self::PrettyCompactMonoBlock
+                | Self::PrettyCompactNoEscapesMonoBlock
+                | Self::PrettySpace
+                | Self::PrettySpaceNoEscapes
+                | Self::PrettySpaceMonoBlock
+                | Self::PrettySpaceNoEscapesMonoBlock
+                | Self::RowBinaryWithNames
+                | Self::RowBinaryWithNamesAndTypes
+                | Self::XML
+                | Self::Markdown
+        )
+    }
+
+    /// Whether the output carries column types.
+    pub const fn has_types(self) -> bool {
+        matches!(
+            self,
+            Self::TabSeparatedWithNamesAndTypes
+                | Self::TabSeparatedRawWithNamesAndTypes
+                | Self::CSVWithNamesAndTypes
+                | Self::CustomSeparatedWithNamesAndTypes
+                | Self::JSON
+                | Self::JSONStrings
+                | Self::JSONColumnsWithMetadata
+                | Self::JSONCompact
+                | Self::JSONCompactStrings
+                | Self::JSONCompactEachRowWithNamesAndTypes
+                | Self::JSONCompactStringsEachRowWithNamesAndTypes
+                | Self::RowBinaryWithNamesAndTypes
+                | Self::Avro
+                | Self::Parquet
+                | Self::ORC
+                | Self::Native
+                | Self::XML
+        )
+    }
+
+    /// Whether the output can be consumed incrementally as chunks arrive,
+    /// without waiting for the whole result.
     ///
-    /// Returns the format name as a static string slice.
-    pub const fn as_str(self) -> &'static str {
+    /// Formats that wrap the result in a single document (e.g. `JSON`,
+    /// `XML`), write a footer (e.g. `Parquet`, `ORC`) or buffer every block
+    /// (`*MonoBlock`) are not streamable.
+    pub const fn is_streamable(self) -> bool {
+        !matches!(
+            self,
+            Self::JSON
+                | Self::JSONStrings
+                | Self::JSONColumns
+                | Self::JSONColumnsWithMetadata
+                | Self::JSONCompact
+                | Self::JSONCompactStrings
+                | Self::JSONCompactColumns
+                | Self::JSONObjectEachRow
+                | Self::PrettyMonoBlock
+                | Self::PrettyNoEscapesMonoBlock
+                | Self::PrettyCompactMonoBlock
+                | Self::PrettyCompactNoEscapesMonoBlock
+                | Self::PrettySpaceMonoBlock
+                | Self::PrettySpaceNoEscapesMonoBlock
+                | Self::Prometheus
+                | Self::ProtobufList
+                | Self::Parquet
+                | Self::ORC
+                | Self::Npy
+                | Self::XML
+        )
+    }
+
+    /// Whether ClickHouse interleaves progress rows with the data.
+    pub const fn has_progress_rows(self) -> bool {
+        matches!(
+            self,
+            Self::JSONEachRowWithProgress | Self::JSONStringsEachRowWithProgress
+        )
+    }
+
+    /// The MIME type of output in this format.
+    pub const fn mime_type(self) -> &'static str {
+        match self {
+            Self::TabSeparated
+            | Self::TabSeparatedRaw
+            | Self::TabSeparatedWithNames
+            | Self::TabSeparatedWithNamesAndTypes
+            | Self::TabSeparatedRawWithNames
+            | Self::TabSeparatedRawWithNamesAndTypes => "text/tab-separated-values",
+            Self::CSV | Self::CSVWithNames | Self::CSVWithNamesAndTypes => "text/csv",
+            Self::JSON
+            | Self::JSONStrings
+            | Self::JSONColumns
+            | Self::JSONColumnsWithMetadata
+            | Self::JSONCompact
+            | Self::JSONCompactStrings
+            | Self::JSONCompactColumns
+            | Self::JSONObjectEachRow => "application/json",
+            Self::JSONEachRow
+            | Self::PrettyJSONEachRow
+            | Self::JSONEachRowWithProgress
+            | Self::JSONStringsEachRow
+            | Self::JSONStringsEachRowWithProgress
+            | Self::JSONCompactEachRow
+            | Self::JSONCompactEachRowWithNames
+            | Self::JSONCompactEachRowWithNamesAndTypes
+            | Self::JSONCompactStringsEachRow
+            | Self::JSONCompactStringsEachRowWithNames
+            | Self::JSONCompactStringsEachRowWithNamesAndTypes => "application/x-ndjson",
+            Self::Parquet => "application/vnd.apache.parquet",
+            Self::Avro => "application/avro",
+            Self::Protobuf | Self::ProtobufSingle | Self::ProtobufList => "application/x-protobuf",
+            Self::MsgPack => "application/x-msgpack",
+            Self::BSONEachRow => "application/bson",
+            Self::XML => "application/xml",
+            Self::Markdown => "text/markdown",
+            Self::Prometheus => "text/plain; version=0.0.4",
+            Self::Template
+            | Self::CustomSeparated
+            | Self::CustomSeparatedWithNames
+            | Self::CustomSeparatedWithNamesAndTypes
+            | Self::Values
+            | Self::TSKV
+            | Self::Pretty
+            | Self::PrettyNoEscapes
+            | Self::PrettyMonoBlock
+            | Self::PrettyNoEscapesMonoBlock
+            | Self::PrettyCompact
+            | Self::PrettyCompactNoEscapes
+            | Self::PrettyCompactMonoBlock
+            | Self::PrettyCompactNoEscapesMonoBlock
+            | Self::PrettySpace
+            | Self::PrettySpaceNoEscapes
+            | Self::PrettySpaceMonoBlock
+            | Self::PrettySpaceNoEscapesMonoBlock
+            | Self::Null
+            | Self::LineAsString
+            | Self::Vertical => "text/plain",
+            Self::ORC
+            | Self::Npy
+            | Self::RowBinary
+            | Self::RowBinaryWithNames
+            | Self::RowBinaryWithNamesAndTypes
+            | Self::Native
+            | Self::CapnProto
+            | Self::RawBLOB => "application/octet-stream",
+        }
+    }
+
+    /// The conventional file extension, without the leading dot.
+    pub const fn file_extension(self) -> &'static str {
         match self {
-            Self::TabSeparated => "TabSeparated",
-            Self::TabSeparatedRaw => "TabSeparatedRaw",
-            Self::TabSeparatedWithNames => "TabSeparatedWithNames",
-            Self::TabSeparatedWithNamesAndTypes => "TabSeparatedWithNamesAndTypes",
-            Self::TabSeparatedRawWithNames => "TabSeparatedRawWithNames",
-            Self::TabSeparatedRawWithNamesAndTypes => "TabSeparatedRawWithNamesAndTypes",
-            Self::Template => "Template",
+            Self::TabSeparated
+            | Self::TabSeparatedRaw
+            | Self::TabSeparatedWithNames
+            | Self::TabSeparatedWithNamesAndTypes
+            | Self::TabSeparatedRawWithNames
+            | Self::TabSeparatedRawWithNamesAndTypes => "tsv",
+            Self::CSV | Self::CSVWithNames | Self::CSVWithNamesAndTypes => "csv",
+            Self::JSON
+            | Self::JSONStrings
+            | Self::JSONColumns
+            | Self::JSONColumnsWithMetadata
+            | Self::JSONCompact
+            | Self::JSONCompactStrings
+            | Self::JSONCompactColumns
+            | Self::JSONObjectEachRow => "json",
+            Self::JSONEachRow
+            | Self::PrettyJSONEachRow
+            | Self::JSONEachRowWithProgress
+            | Self::JSONStringsEachRow
+            | Self::JSONStringsEachRowWithProgress
+            | Self::JSONCompactEachRow
+            | Self::JSONCompactEachRowWithNames
+            | Self::JSONCompactEachRowWithNamesAndTypes
+            | Self::JSONCompactStringsEachRow
+            | Self::JSONCompactStringsEachRowWithNames
+            | Self::JSONCompactStringsEachRowWithNamesAndTypes => "ndjson",
+            Self::Parquet => "parquet",
+            Self::ORC => "orc",
+            Self::Avro => "avro",
+            Self::Npy => "npy",
+            Self::Protobuf | Self::ProtobufSingle | Self::ProtobufList => "pb",
+            Self::CapnProto => "capnp",
+            Self::MsgPack => "msgpack",
+            Self::BSONEachRow => "bson",
+            Self::Native => "native",
+            Self::XML => "xml",
+            Self::Markdown => "md",
+            Self::RowBinary
+            | Self::RowBinaryWithNames
+            | Self::RowBinaryWithNamesAndTypes
+            | Self::RawBLOB => "bin",
+            Self::Template
+            | Self::CustomSeparated
+            | Self::CustomSeparatedWithNames
+            | Self::CustomSeparatedWithNamesAndTypes
+            | Self::Values
+            | Self::TSKV
+            | Self::Pretty
+            | Self::PrettyNoEscapes
+            | Self::PrettyMonoBlock
+            | Self::PrettyNoEscapesMonoBlock
+            | Self::PrettyCompact
+            | Self::PrettyCompactNoEscapes
+            | Self::PrettyCompactMonoBlock
+            | Self::PrettyCompactNoEscapesMonoBlock
+            | Self::PrettySpace
+            | Self::PrettySpaceNoEscapes
+            | Self::PrettySpaceMonoBlock
+            | Self::PrettySpaceNoEscapesMonoBlock
+            | Self::Prometheus
+            | Self::Null
+            | Self::LineAsString
+            | Self::Vertical => "txt",
+        }
+    }
+}
+
+/// How `DateTime` values are rendered in text output formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateTimeOutputFormat {
+    /// `2024-01-02 03:04:05`
+    Simple,
+    /// `2024-01-02T03:04:05Z`
+    Iso,
+    /// `1704164645`
+    UnixTimestamp,
+}
+
+impl DateTimeOutputFormat {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Simple => "simple",
+            Self::Iso => "iso",
+            Self::UnixTimestamp => "unix_timestamp",
+        }
+    }
+}
+
+/// Compression codec used inside Parquet files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParquetCompression {
+    None,
+    Snappy,
+    Zstd,
+    Lz4,
+    Gzip,
+    Brotli,
+}
+
+impl ParquetCompression {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Snappy => "snappy",
+            Self::Zstd => "zstd",
+            Self::Lz4 => "lz4",
+            Self::Gzip => "gzip",
+            Self::Brotli => "brotli",
+        }
+    }
+}
+
+/// How fields are escaped in `CustomSeparated` formats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapingRule {
+    Escaped,
+    Quoted,
+    CSV,
+    JSON,
+    XML,
+    Raw,
+}
+
+impl EscapingRule {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Escaped => "Escaped",
+            Self::Quoted => "Quoted",
             Self::CSV => "CSV",
-            Self::CSVWithNames => "CSVWithNames",
-            Self::CSVWithNamesAndTypes => "CSVWithNamesAndTypes",
-            Self::CustomSeparated => "CustomSeparated",
-            Self::CustomSeparatedWithNames => "CustomSeparatedWithNames",
-            Self::CustomSeparatedWithNamesAndTypes => "CustomSeparatedWithNamesAndTypes",
-            Self::Values => "Values",
             Self::JSON => "JSON",
-            Self::JSONStrings => "JSONStrings",
-            Self::JSONColumns => "JSONColumns",
-            Self::JSONColumnsWithMetadata => "JSONColumnsWithMetadata",
-            Self::JSONCompact => "JSONCompact",
-            Self::JSONCompactStrings => "JSONCompactStrings",
-            Self::JSONCompactColumns => "JSONCompactColumns",
-            Self::JSONEachRow => "JSONEachRow",
-            Self::PrettyJSONEachRow => "PrettyJSONEachRow",
-            Self::JSONEachRowWithProgress => "JSONEachRowWithProgress",
-            Self::JSONStringsEachRow => "JSONStringsEachRow",
-            Self::JSONStringsEachRowWithProgress => "JSONStringsEachRowWithProgress",
-            Self::JSONCompactEachRow => "JSONCompactEachRow",
-            Self::JSONCompactEachRowWithNames => "JSONCompactEachRowWithNames",
-            Self::JSONCompactEachRowWithNamesAndTypes => "JSONCompactEachRowWithNamesAndTypes",
-            Self::JSONCompactStringsEachRow => "JSONCompactStringsEachRow",
-            Self::JSONCompactStringsEachRowWithNames => "JSONCompactStringsEachRowWithNames",
-            Self::JSONCompactStringsEachRowWithNamesAndTypes => {
-                "JSONCompactStringsEachRowWithNamesAndTypes"
-            }
-            Self::JSONObjectEachRow => "JSONObjectEachRow",
-            Self::BSONEachRow => "BSONEachRow",
-            Self::TSKV => "TSKV",
-            Self::Pretty => "Pretty",
-            Self::PrettyNoEscapes => "PrettyNoEscapes",
-            Self::PrettyMonoBlock => "PrettyMonoBlock",
-            Self::PrettyNoEscapesMonoBlock => "PrettyNoEscapesMonoBlock",
-            Self::PrettyCompact => "PrettyCompact",
-            Self::PrettyCompactNoEscapes => "PrettyCompactNoEscapes",
-            Self::PrettyCompactMonoBlock => "PrettyCompactMonoBlock",
-            Self::PrettyCompactNoEscapesMonoBlock => "PrettyCompactNoEscapesMonoBlock",
-            Self::PrettySpace => "PrettySpace",
-            Self::PrettySpaceNoEscapes => "PrettySpaceNoEscapes",
-            Self::PrettySpaceMonoBlock => "PrettySpaceMonoBlock",
-            Self::PrettySpaceNoEscapesMonoBlock => "PrettySpaceNoEscapesMonoBlock",
-            Self::Prometheus => "Prometheus",
-            Self::Protobuf => "Protobuf",
-            Self::ProtobufSingle => "ProtobufSingle",
-            Self::ProtobufList => "ProtobufList",
-            Self::Avro => "Avro",
-            Self::Parquet => "Parquet",
-            Self::ORC => "ORC",
-            Self::Npy => "Npy",
-            Self::RowBinary => "RowBinary",
-            Self::RowBinaryWithNames => "RowBinaryWithNames",
-            Self::RowBinaryWithNamesAndTypes => "RowBinaryWithNamesAndTypes",
-            Self::Native => "Native",
-            Self::Null => "Null",
             Self::XML => "XML",
-            Self::CapnProto => "CapnProto",
-            Self::LineAsString => "LineAsString",
-            Self::RawBLOB => "RawBLOB",
-            Self::MsgPack => "MsgPack",
-            Self::Markdown => "Markdown",
-            Self::Vertical => "Vertical",
+            Self::Raw => "Raw",
+        }
+    }
+}
+
+/// Per-query format settings, such as the CSV delimiter or the
+/// `CustomSeparated` and `Template` layouts.
+///
+/// Options are rendered as a trailing `SETTINGS` clause, so they only apply to
+/// the query they are attached to. Attach them with
+/// [`Arg::FormatOptions`](crate::arg::Arg::FormatOptions) or
+/// [`Connection::query_with_options`](crate::connection::Connection::query_with_options).
+/// Settings that do not apply to the chosen format are ignored by chDB.
+///
+/// # Examples
+///
+/// ```no_run
+/// use chdb_rust::arg::Arg;
+/// use chdb_rust::format::{EscapingRule, FormatOptions, OutputFormat};
+///
+/// let options = FormatOptions::new()
+///     .csv_delimiter(';')
+///     .crlf_line_endings(true)
+///     .null_representation("NULL");
+/// let result = chdb_rust::execute(
+///     "SELECT number, NULL FROM numbers(3)",
+///     Some(&[Arg::OutputFormat(OutputFormat::CSV), Arg::FormatOptions(options)]),
+/// )?;
+///
+/// let options = FormatOptions::new()
+///     .custom_escaping_rule(EscapingRule::Quoted)
+///     .custom_field_delimiter(" | ")
+///     .custom_row_after_delimiter(";\n");
+/// let result = chdb_rust::execute(
+///     "SELECT number, toString(number) FROM numbers(3)",
+///     Some(&[Arg::OutputFormat(OutputFormat::CustomSeparated), Arg::FormatOptions(options)]),
+/// )?;
+/// # Ok::<(), chdb_rust::error::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormatOptions {
+    settings: Vec<(String, Value)>,
+}
+
+impl FormatOptions {
+    /// Create an empty set of options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set an arbitrary format setting, e.g. `("output_format_json_named_tuples_as_objects", 1)`.
+    ///
+    /// Setting the same name twice keeps the last value. Names other than
+    /// ASCII letters, digits and `_` are rejected with
+    /// [`Error::InvalidData`] when the options are used.
+    pub fn setting(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        let name = name.into();
+        let value = value.into();
+        match self.settings.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.settings.push((name, value)),
         }
+        self
+    }
+
+    /// The CSV field delimiter (`format_csv_delimiter`). Defaults to `,`.
+    pub fn csv_delimiter(self, delimiter: char) -> Self {
+        self.setting("format_csv_delimiter", delimiter.to_string())
+    }
+
+    /// Whether strings in single quotes are parsed as quoted CSV fields
+    /// (`format_csv_allow_single_quotes`).
+    pub fn csv_allow_single_quotes(self, value: bool) -> Self {
+        self.setting("format_csv_allow_single_quotes", value)
+    }
+
+    /// Whether strings in double quotes are parsed as quoted CSV fields
+    /// (`format_csv_allow_double_quotes`).
+    pub fn csv_allow_double_quotes(self, value: bool) -> Self {
+        self.setting("format_csv_allow_double_quotes", value)
+    }
+
+    /// End CSV and TSV rows with `\r\n` instead of `\n`.
+    pub fn crlf_line_endings(self, value: bool) -> Self {
+        self.setting("output_format_csv_crlf_end_of_line", value)
+            .setting("output_format_tsv_crlf_end_of_line", value)
+    }
+
+    /// How `NULL` is written in CSV and TSV output, e.g. `NULL` or an empty string.
+    pub fn null_representation(self, value: impl Into<String>) -> Self {
+        let value = value.into();
+        self.setting("format_csv_null_representation", value.clone())
+            .setting("format_tsv_null_representation", value)
+    }
+
+    /// Quote 64-bit integers in JSON output so they survive JavaScript parsers
+    /// (`output_format_json_quote_64bit_integers`).
+    pub fn json_quote_64bit_integers(self, value: bool) -> Self {
+        self.setting("output_format_json_quote_64bit_integers", value)
+    }
+
+    /// How `DateTime` values are written (`date_time_output_format`).
+    pub fn date_time_output_format(self, format: DateTimeOutputFormat) -> Self {
+        self.setting("date_time_output_format", format.as_str())
+    }
+
+    /// The compression codec for Parquet output
+    /// (`output_format_parquet_compression_method`).
+    pub fn parquet_compression(self, compression: ParquetCompression) -> Self {
+        self.setting(
+            "output_format_parquet_compression_method",
+            compression.as_str(),
+        )
+    }
+
+    /// How fields are escaped in `CustomSeparated` formats (`format_custom_escaping_rule`).
+    pub fn custom_escaping_rule(self, rule: EscapingRule) -> Self {
+        self.setting("format_custom_escaping_rule", rule.as_str())
+    }
+
+    /// The delimiter between fields in `CustomSeparated` formats.
+    pub fn custom_field_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_custom_field_delimiter", value.into())
+    }
+
+    /// The text written before each row in `CustomSeparated` formats.
+    pub fn custom_row_before_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_custom_row_before_delimiter", value.into())
+    }
+
+    /// The text written after each row in `CustomSeparated` formats.
+    pub fn custom_row_after_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_custom_row_after_delimiter", value.into())
+    }
+
+    /// The text written between rows in `CustomSeparated` formats.
+    pub fn custom_row_between_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_custom_row_between_delimiter", value.into())
+    }
+
+    /// The text written before the result set in `CustomSeparated` formats.
+    pub fn custom_result_before_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_custom_result_before_delimiter", value.into())
+    }
+
+    /// The text written after the result set in `CustomSeparated` formats.
+    pub fn custom_result_after_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_custom_result_after_delimiter", value.into())
+    }
+
+    /// The `Template` row format, e.g. `id: ${id:Quoted}, name: ${name:Escaped}`
+    /// (`format_template_row_format`).
+    pub fn template_row_format(self, value: impl Into<String>) -> Self {
+        self.setting("format_template_row_format", value.into())
+    }
+
+    /// The `Template` result set format, which must contain `${data}`
+    /// (`format_template_resultset_format`).
+    pub fn template_resultset_format(self, value: impl Into<String>) -> Self {
+        self.setting("format_template_resultset_format", value.into())
+    }
+
+    /// Read the `Template` row format from a file (`format_template_row`).
+    pub fn template_row_file(self, path: impl Into<String>) -> Self {
+        self.setting("format_template_row", path.into())
+    }
+
+    /// Read the `Template` result set format from a file (`format_template_resultset`).
+    pub fn template_resultset_file(self, path: impl Into<String>) -> Self {
+        self.setting("format_template_resultset", path.into())
+    }
+
+    /// The delimiter between rows in the `Template` format
+    /// (`format_template_rows_between_delimiter`).
+    pub fn template_rows_between_delimiter(self, value: impl Into<String>) -> Self {
+        self.setting("format_template_rows_between_delimiter", value.into())
+    }
+
+    /// The configured settings, in the order they were first set.
+    pub fn settings(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.settings.iter().map(|(n, v)| (n.as_str(), v))
+    }
+
+    /// Whether no options are set.
+    pub fn is_empty(&self) -> bool {
+        self.settings.is_empty()
+    }
+
+    /// The options as `--name=value` command-line arguments, which set them
+    /// for every query on a connection.
+    pub(crate) fn to_args(&self) -> Result<Vec<String>, Error> {
+        self.settings
+            .iter()
+            .map(|(name, value)| {
+                check_setting_name(name)?;
+                Ok(match value {
+                    Value::String(s) => format!("--{name}={s}"),
+                    Value::Bool(b) => format!("--{name}={}", u8::from(*b)),
+                    other => format!("--{name}={}", other.to_sql()),
+                })
+            })
+            .collect()
+    }
+
+    /// Append the options to `sql` as a `SETTINGS` clause, merged into the
+    /// query's own clause if it has one.
+    pub(crate) fn apply(&self, sql: &str) -> Result<String, Error> {
+        append_settings(sql, &self.settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_options_to_args() {
+        let options = FormatOptions::new()
+            .csv_delimiter(';')
+            .crlf_line_endings(true)
+            .null_representation("N'A");
+        assert_eq!(
+            options.to_args().unwrap(),
+            [
+                "--format_csv_delimiter=;",
+                "--output_format_csv_crlf_end_of_line=1",
+                "--output_format_tsv_crlf_end_of_line=1",
+                "--format_csv_null_representation=N'A",
+                "--format_tsv_null_representation=N'A",
+            ]
+        );
+
+        let options = FormatOptions::new().setting("a=1 --b", 1);
+        assert!(options.to_args().is_err());
+        assert!(options.apply("SELECT 1").is_err());
+    }
+
+    #[test]
+    fn test_input_format_from_path() {
+        assert!(matches!(
+            InputFormat::from_path("a/b.CSV"),
+            Some(InputFormat::CSV)
+        ));
+        assert!(matches!(
+            InputFormat::from_path("logs/*.ndjson.zst"),
+            Some(InputFormat::JSONEachRow)
+        ));
+        assert!(matches!(
+            InputFormat::from_path("part_{1..3}.parquet"),
+            Some(InputFormat::Parquet)
+        ));
+        assert!(InputFormat::from_path("README").is_none());
+        assert!(InputFormat::from_path("data.gz").is_none());
+    }
+
+    #[test]
+    fn test_from_str_and_display() {
+        for format in OutputFormat::all() {
+            assert_eq!(format.to_string().parse::<OutputFormat>().unwrap(), format);
+        }
+        for format in InputFormat::all() {
+            assert_eq!(format.as_str().parse::<InputFormat>().unwrap(), format);
+        }
+        assert_eq!(
+            "tsv".parse::<InputFormat>().unwrap(),
+            InputFormat::TabSeparated
+        );
+        assert_eq!(
+            "ndjson".parse::<OutputFormat>().unwrap(),
+            OutputFormat::JSONEachRow
+        );
+        assert!("Nope".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_capabilities() {
+        assert!(OutputFormat::Parquet.is_binary());
+        assert!(!OutputFormat::Parquet.is_streamable());
+        assert!(OutputFormat::CSVWithNamesAndTypes.has_header());
+        assert!(OutputFormat::CSVWithNamesAndTypes.has_types());
+        assert!(!OutputFormat::CSV.has_header());
+        assert!(InputFormat::JSONEachRow.is_streamable());
+        assert_eq!(OutputFormat::CSV.mime_type(), "text/csv");
+    }
+
+    #[test]
+    fn test_file_extension_round_trip() {
+        for format in [
+            OutputFormat::CSV,
+            OutputFormat::TabSeparated,
+            OutputFormat::JSONEachRow,
+            OutputFormat::Parquet,
+            OutputFormat::ORC,
+            OutputFormat::Avro,
+        ] {
+            assert_eq!(
+                OutputFormat::from_extension(format.file_extension()),
+                Some(format)
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_options() {
+        let options = FormatOptions::new()
+            .csv_delimiter(';')
+            .null_representation("\\N")
+            .csv_delimiter('|')
+            .parquet_compression(ParquetCompression::Zstd);
+        assert_eq!(
+            options.apply("SELECT 1;\n").unwrap(),
+            "SELECT 1\nSETTINGS format_csv_delimiter = '|', \
+             format_csv_null_representation = '\\\\N', \
+             format_tsv_null_representation = '\\\\N', \
+             output_format_parquet_compression_method = 'zstd'"
+        );
+        assert_eq!(FormatOptions::new().apply("SELECT 1").unwrap(), "SELECT 1");
+    }
+
+    #[test]
+    fn test_split_extension() {
+        assert_eq!(
+            split_extension("out/export.parquet.zst"),
+            Some(("parquet", Some(Compression::Zstd)))
+        );
+        assert_eq!(split_extension("export.csv"), Some(("csv", None)));
+        assert!(matches!(
+            OutputFormat::from_path("export.ndjson.gz"),
+            Some(OutputFormat::JSONEachRow)
+        ));
     }
 }
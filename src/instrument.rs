@@ -0,0 +1,402 @@
+//! Tracing spans and metrics around chDB calls.
+//!
+//! With the `tracing` feature, queries, streams, inserts and Arrow
+//! registrations run inside spans under the `chdb` target:
+//!
+//! | Span | Fields |
+//! |------|--------|
+//! | `chdb.query` | `sql_hash`, `format`, `query_id`, `rows_read`, `bytes_read`, `bytes_returned`, `elapsed_ms`, `error` |
+//! | `chdb.stream` | as `chdb.query`, and `cancelled`; statistics are summed over chunks and `elapsed_ms` is wall-clock time |
+//! | `chdb.insert` | `table`, `rows`, `error` |
+//! | `chdb.arrow` | `operation` (`register`/`unregister`), `table`, `error` |
+//!
+//! `sql_hash` identifies the statement with literals and whitespace
+//! normalized away, so the same query with different parameters shares a
+//! hash. The statement text itself is not recorded. `query_id` is only set
+//! for queries run with
+//! [`Connection::query_with_id`](crate::connection::Connection::query_with_id).
+//!
+//! With the `metrics` feature, the following are reported through the
+//! [`metrics`](https://docs.rs/metrics) facade, labeled by `operation`
+//! (`query`, `stream`, `insert` or `arrow`):
+//!
+//! - `chdb_queries_total`: counter of calls
+//! - `chdb_query_errors_total`: counter of failed calls, also labeled by
+//!   `code`, the ClickHouse [`ErrorCode`](crate::error::ErrorCode) or `none`
+//! - `chdb_queries_cancelled_total`: counter of streams dropped or cancelled
+//!   before they were exhausted
+//! - `chdb_query_duration_seconds`: histogram of wall-clock latency
+//! - `chdb_query_bytes_returned`: histogram of result sizes
+
+#[cfg(feature = "metrics")]
+use std::time::Instant;
+
+use crate::error::{Error, Result};
+use crate::format::OutputFormat;
+use crate::hash::fnv1a;
+use crate::query_result::QueryResult;
+
+#[cfg(feature = "tracing")]
+type Span = tracing::Span;
+#[cfg(not(feature = "tracing"))]
+type Span = ();
+
+/// A `chdb`-target debug span, or `()` without the `tracing` feature.
+#[cfg(feature = "tracing")]
+macro_rules! span {
+    ($($args:tt)*) => {
+        tracing::debug_span!(target: "chdb", $($args)*)
+    };
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! span {
+    ($($args:tt)*) => {
+        ()
+    };
+}
+
+/// Statistics of a finished call.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Stats {
+    pub(crate) rows_read: u64,
+    pub(crate) bytes_read: u64,
+    pub(crate) bytes_returned: u64,
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    pub(crate) elapsed_secs: f64,
+}
+
+impl Stats {
+    pub(crate) fn of(result: &QueryResult) -> Self {
+        Self {
+            rows_read: result.rows_read(),
+            bytes_read: result.bytes_read(),
+            bytes_returned: result.data_ref().len() as u64,
+            elapsed_secs: result.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// Add the statistics of one more streamed chunk.
+    ///
+    /// The elapsed time is left alone: chunks do not report it consistently,
+    /// so streams measure it themselves.
+    pub(crate) fn add(&mut self, chunk: &QueryResult) {
+        let chunk = Self::of(chunk);
+        self.rows_read += chunk.rows_read;
+        self.bytes_read += chunk.bytes_read;
+        self.bytes_returned += chunk.bytes_returned;
+    }
+}
+
+/// An instrumented call: a span and/or a latency measurement.
+///
+/// Without the `tracing` and `metrics` features, this is a no-op.
+#[derive(Debug)]
+pub(crate) struct Instrument {
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    span: Span,
+    #[cfg(feature = "metrics")]
+    operation: &'static str,
+    #[cfg(feature = "metrics")]
+    start: Instant,
+}
+
+#[cfg_attr(
+    not(all(feature = "tracing", feature = "metrics")),
+    allow(unused_variables)
+)]
+impl Instrument {
+    fn new(operation: &'static str, span: Span) -> Self {
+        #[cfg(feature = "metrics")]
+        metrics::counter!("chdb_queries_total", "operation" => operation).increment(1);
+        Self {
+            span,
+            #[cfg(feature = "metrics")]
+            operation,
+            #[cfg(feature = "metrics")]
+            start: Instant::now(),
+        }
+    }
+
+    /// A query whose result is returned at once.
+    pub(crate) fn query(sql: &str, format: OutputFormat) -> Self {
+        Self::new(
+            "query",
+            span!(
+                "chdb.query",
+                sql_hash = %format_args!("{:016x}", normalized_query_hash(sql)),
+                format = format.as_str(),
+                query_id = tracing::field::Empty,
+                rows_read = tracing::field::Empty,
+                bytes_read = tracing::field::Empty,
+                bytes_returned = tracing::field::Empty,
+                elapsed_ms = tracing::field::Empty,
+                error = tracing::field::Empty,
+            ),
+        )
+    }
+
+    /// A query whose result is streamed chunk by chunk.
+    pub(crate) fn stream(sql: &str, format: OutputFormat) -> Self {
+        Self::new(
+            "stream",
+            span!(
+                "chdb.stream",
+                sql_hash = %format_args!("{:016x}", normalized_query_hash(sql)),
+                format = format.as_str(),
+                query_id = tracing::field::Empty,
+                rows_read = tracing::field::Empty,
+                bytes_read = tracing::field::Empty,
+                bytes_returned = tracing::field::Empty,
+                elapsed_ms = tracing::field::Empty,
+                cancelled = tracing::field::Empty,
+                error = tracing::field::Empty,
+            ),
+        )
+    }
+
+    /// An insert of `rows` typed rows into `table`.
+    pub(crate) fn insert(table: &str, rows: usize) -> Self {
+        Self::new(
+            "insert",
+            span!("chdb.insert", table, rows, error = tracing::field::Empty,),
+        )
+    }
+
+    /// Registration or unregistration of an Arrow table.
+    pub(crate) fn arrow(operation: &'static str, table: &str) -> Self {
+        Self::new(
+            "arrow",
+            span!(
+                "chdb.arrow",
+                operation,
+                table,
+                error = tracing::field::Empty,
+            ),
+        )
+    }
+
+    /// Record the id a query was tagged with.
+    pub(crate) fn record_query_id(&self, query_id: &str) {
+        #[cfg(feature = "tracing")]
+        self.span.record("query_id", query_id);
+    }
+
+    /// Run `f` inside the span.
+    pub(crate) fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tracing")]
+        return self.span.in_scope(f);
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+
+    /// Record the outcome of a query returning a [`QueryResult`].
+    pub(crate) fn finish_query(self, result: &Result<QueryResult>) {
+        match result {
+            Ok(result) => self.succeed(Stats::of(result)),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    /// Record the outcome of a call that returns no result.
+    pub(crate) fn finish<T>(self, result: &Result<T>) {
+        match result {
+            Ok(_) => self.succeed(Stats::default()),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    pub(crate) fn succeed(self, stats: Stats) {
+        self.record_stats(stats);
+        #[cfg(feature = "metrics")]
+        {
+            self.record_latency();
+            metrics::histogram!("chdb_query_bytes_returned", "operation" => self.operation)
+                .record(stats.bytes_returned as f64);
+        }
+    }
+
+    /// Record a stream given up before it was exhausted, with what it
+    /// returned until then.
+    pub(crate) fn cancel(self, stats: Stats) {
+        self.record_stats(stats);
+        #[cfg(feature = "tracing")]
+        self.span.record("cancelled", true);
+        #[cfg(feature = "metrics")]
+        {
+            self.record_latency();
+            metrics::counter!("chdb_queries_cancelled_total", "operation" => self.operation)
+                .increment(1);
+        }
+    }
+
+    fn record_stats(&self, stats: Stats) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span.record("rows_read", stats.rows_read);
+            self.span.record("bytes_read", stats.bytes_read);
+            self.span.record("bytes_returned", stats.bytes_returned);
+            self.span.record("elapsed_ms", stats.elapsed_secs * 1000.0);
+        }
+    }
+
+    pub(crate) fn fail(self, error: &Error) {
+        #[cfg(feature = "tracing")]
+        self.span.record("error", tracing::field::display(error));
+        #[cfg(feature = "metrics")]
+        {
+            self.record_latency();
+            let code = error.code().map_or("none".to_string(), |c| c.to_string());
+            metrics::counter!(
+                "chdb_query_errors_total",
+                "operation" => self.operation,
+                "code" => code,
+            )
+            .increment(1);
+        }
+    }
+
+    #[cfg(feature = "metrics")]
+    fn record_latency(&self) {
+        metrics::histogram!("chdb_query_duration_seconds", "operation" => self.operation)
+            .record(self.start.elapsed().as_secs_f64());
+    }
+}
+
+/// The `sql_hash` recorded on spans: a hash of `sql` with literals, comments
+/// and redundant whitespace removed.
+///
+/// The hash uses 64-bit FNV-1a, so it is stable across builds and platforms.
+///
+/// # Examples
+///
+/// ```
+/// use chdb_rust::instrument::normalized_query_hash;
+///
+/// assert_eq!(
+///     normalized_query_hash("SELECT * FROM t WHERE id = 1"),
+///     normalized_query_hash("SELECT *  FROM t WHERE id = 42"),
+/// );
+/// ```
+pub fn normalized_query_hash(sql: &str) -> u64 {
+    fnv1a(normalize_query(sql).as_bytes())
+}
+
+/// Replace string and numeric literals with `?`, drop comments, and keep a
+/// single space only where whitespace separates two words or literals.
+fn normalize_query(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut space = false;
+    let mut prev_word = false;
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let token = match chars[i] {
+            c if c.is_whitespace() => {
+                space = true;
+                i += 1;
+                continue;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                space = true;
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                space = true;
+                continue;
+            }
+            '\'' => {
+                i += 1;
+                while i < chars.len() {
+                    match chars[i] {
+                        '\\' => i += 2,
+                        // A doubled quote is an escaped quote.
+                        '\'' if chars.get(i + 1) == Some(&'\'') => i += 2,
+                        '\'' => break,
+                        _ => i += 1,
+                    }
+                }
+                i += 1;
+                "?"
+            }
+            c if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+                "?"
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                ""
+            }
+            _ => {
+                i += 1;
+                ""
+            }
+        };
+        let word = token == "?" || chars[start].is_alphabetic() || chars[start] == '_';
+        if space && prev_word && word {
+            out.push(' ');
+        }
+        space = false;
+        prev_word = word;
+        if token.is_empty() {
+            out.extend(&chars[start..i.min(chars.len())]);
+        } else {
+            out.push_str(token);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("SELECT *\n  FROM t -- users\n WHERE id = 42 AND name = 'it''s'"),
+            "SELECT*FROM t WHERE id=? AND name=?"
+        );
+        assert_eq!(
+            normalize_query("SELECT x1, 1.5e3 /* c */ FROM t"),
+            "SELECT x1,? FROM t"
+        );
+    }
+
+    #[test]
+    fn test_normalized_query_hash() {
+        let hash = normalized_query_hash("SELECT * FROM t WHERE id = 1 AND name = 'a'");
+        assert_eq!(
+            hash,
+            normalized_query_hash("SELECT *\n  FROM t -- users\n WHERE id = 42 AND name = 'it''s'")
+        );
+        assert_eq!(
+            hash,
+            normalized_query_hash("  SELECT * FROM t /* x */ WHERE id = 0x1F AND name = 'b'  ")
+        );
+        assert_ne!(
+            hash,
+            normalized_query_hash("SELECT * FROM t2 WHERE id = 1 AND name = 'a'")
+        );
+        assert_ne!(
+            hash,
+            normalized_query_hash("SELECT * FROM t WHERE id > 1 AND name = 'a'")
+        );
+        assert_ne!(
+            normalized_query_hash("SELECT a b"),
+            normalized_query_hash("SELECT ab")
+        );
+    }
+}
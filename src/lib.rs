@@ -24,7 +24,25 @@
 //!
 //! - **Stateless queries**: Execute one-off queries without persistent storage
 //! - **Stateful sessions**: Create databases and tables with persistent storage
-//! - **Multiple output formats**: JSON, CSV, TabSeparated, and more
+//! - **Multiple output formats**: JSON, CSV, TabSeparated, and more, with per-query `FormatOptions`, parseable from their chDB names and serializable with the `serde` feature
+//! - **Query builders**: Compose `SELECT`, `INSERT` and `CREATE TABLE` statements with quoted identifiers and bound values
+//! - **Migrations**: Apply versioned schema migrations to persistent sessions
+//! - **Streaming results**: Stream large results chunk by chunk into any `Write` sink or file, optionally compressed with gzip, zstd, lz4 or brotli
+//! - **Table functions**: Read local files, URLs and S3 objects with `file()`, `url()` and `s3()` helpers
+//! - **Typed rows**: Map structs to tables with `#[derive(ChdbRow)]` (`derive` feature) and `RowBinary`
+//! - **Library info**: Report the version and path of the loaded libchdb with [`library_info`] and [`version`], checked against the bindings on first connect
+//! - **Dynamic loading**: Load libchdb at runtime with `load_library` instead of linking it (`dynamic-loading` feature)
+//! - **Snapshots**: Save prepared sessions and start temporary sessions from copies of them
+//! - **Backups**: Full or incremental `BACKUP`/`RESTORE` of tables, databases or whole sessions
+//! - **Data directory locking**: Keep two processes from opening the same session directory
+//! - **Temporary sessions**: Sessions in auto-created temp directories, removed on drop
+//! - **Read-only sessions**: Open persistent stores with writes and DDL rejected up front
+//! - **Connection pool**: Run concurrent queries over the single chDB connection with a bounded `Pool`
+//! - **Query plans**: Structured `EXPLAIN` output, including primary key and index usage
+//! - **Progress reporting**: Observe rows read and estimated totals while long queries run
+//! - **Query log**: Tag queries with ids and read their profile from `system.query_log`
+//! - **Observability**: Tracing spans and metrics around every query (`tracing`/`metrics` features)
+//! - **Log capture**: Route chDB's internal logs to the `log` or `tracing` crates (`log`/`tracing` features)
 //! - **Thread-safe**: Connections and results can be safely sent between threads
 //!
 //! ## Examples
@@ -38,6 +56,8 @@
 
 pub mod arg;
 pub mod arrow_stream;
+pub mod backup;
+#[cfg(not(feature = "dynamic-loading"))]
 #[allow(
     dead_code,
     unused,
@@ -45,15 +65,49 @@ pub mod arrow_stream;
     non_camel_case_types,
     non_upper_case_globals
 )]
-mod bindings;
+mod bindings {
+    // Generated by build.rs from the chdb.h it found or downloaded. The
+    // checked-in src/bindings.rs is used for docs.rs and `vendored-bindings`.
+    #[cfg(chdb_generated_bindings)]
+    include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+    #[cfg(not(chdb_generated_bindings))]
+    include!("bindings.rs");
+}
+#[cfg(feature = "dynamic-loading")]
+use dynamic::bindings;
 pub mod connection;
+mod dir_lock;
+#[cfg(feature = "dynamic-loading")]
+pub mod dynamic;
 pub mod error;
+pub mod explain;
 pub mod format;
+mod hash;
+pub mod instrument;
+mod json;
+pub mod library;
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub mod log_capture;
 pub mod log_level;
+pub mod migrations;
+pub mod pool;
+pub mod progress;
+pub mod query_builder;
+pub mod query_log;
 pub mod query_result;
+pub mod row;
 pub mod session;
+pub mod snapshot;
+pub mod stream;
+pub mod table_function;
+
+#[cfg(feature = "derive")]
+pub use chdb_rust_derive::ChdbRow;
+#[cfg(feature = "dynamic-loading")]
+pub use dynamic::load_library;
+pub use library::{library_info, version};
 
-use crate::arg::{extract_output_format, Arg};
+use crate::arg::{extract_format_options, extract_output_format, Arg};
 use crate::connection::Connection;
 use crate::error::Result;
 use crate::query_result::QueryResult;
@@ -102,5 +156,8 @@ use crate::query_result::QueryResult;
 pub fn execute(query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult> {
     let conn = Connection::open_in_memory()?;
     let fmt = extract_output_format(query_args);
-    conn.query(query, fmt)
+    match extract_format_options(query_args) {
+        Some(options) => conn.query_with_options(query, fmt, options),
+        None => conn.query(query, fmt),
+    }
 }
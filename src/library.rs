@@ -0,0 +1,288 @@
+//! Information about the libchdb shared library loaded at runtime.
+//!
+//! The crate may be built against one libchdb and run against another, e.g.
+//! when `LD_LIBRARY_PATH` points elsewhere. [`library_info`] and [`version`]
+//! report what was actually loaded.
+//!
+//! The first [`Connection::open`] checks the loaded version against the
+//! bindings: libchdb older than [`MIN_SUPPORTED_VERSION`] is rejected with
+//! [`Error::IncompatibleLibrary`], and a release other than
+//! [`BINDINGS_VERSION`] is reported once as a warning through `tracing` or
+//! `log`, when those features are enabled, and by
+//! [`LibraryInfo::matches_bindings`]. APIs added after the minimum, such as
+//! Arrow scans, return [`Error::Unsupported`] on older libraries. With the
+//! `dynamic-loading` feature this avoids calling a missing symbol; when
+//! libchdb is linked at build time, the dynamic linker already refuses to
+//! start a program against a libchdb that lacks one.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! let info = chdb_rust::library_info()?;
+//! println!("libchdb {} from {:?}", info.version, info.path);
+//!
+//! if chdb_rust::version()? >= chdb_rust::library::ARROW_SCAN_MIN_VERSION {
+//!     // Arrow scans are available.
+//! }
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::format::OutputFormat;
+
+static VERSION: OnceLock<String> = OnceLock::new();
+
+/// The libchdb release the bindings were generated from.
+pub const BINDINGS_VERSION: Version = Version::new(26, 1, 0);
+
+/// The oldest libchdb providing the `chdb_connect`/`chdb_result_*` API.
+pub const MIN_SUPPORTED_VERSION: Version = Version::new(25, 1, 0);
+
+/// The oldest libchdb providing `chdb_arrow_scan` and related functions.
+pub const ARROW_SCAN_MIN_VERSION: Version = Version::new(25, 8, 0);
+
+/// A libchdb version, compared by its first three components.
+///
+/// ClickHouse reports four components (e.g. `26.1.2.5`); the fourth, the
+/// build number, is ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Create a version from its components.
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for Version {
+    type Err = Error;
+
+    /// Parse `26.1`, `26.1.0`, `v26.1.0` or `26.1.2.5`.
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidData(format!("Invalid libchdb version '{s}'"));
+        let mut parts = s.trim().trim_start_matches('v').split('.');
+        let mut next = |required: bool| match parts.next() {
+            Some(part) => part.parse::<u32>().map_err(|_| invalid()),
+            None if required => Err(invalid()),
+            None => Ok(0),
+        };
+        Ok(Self::new(next(true)?, next(true)?, next(false)?))
+    }
+}
+
+/// The version of the loaded libchdb.
+///
+/// # Errors
+///
+/// Returns an error if the version cannot be detected or parsed.
+pub fn version() -> Result<Version> {
+    library_info()?.version.parse()
+}
+
+/// The loaded libchdb.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryInfo {
+    /// The ClickHouse version libchdb reports, e.g. `26.1.1.1`.
+    pub version: String,
+    /// The path the shared library was loaded from, if it can be determined.
+    pub path: Option<PathBuf>,
+}
+
+impl LibraryInfo {
+    /// Whether the loaded libchdb is the release the bindings were generated
+    /// from, comparing major and minor versions.
+    ///
+    /// Returns `false` if the version cannot be parsed.
+    pub fn matches_bindings(&self) -> bool {
+        self.version
+            .parse::<Version>()
+            .is_ok_and(|v| (v.major, v.minor) == (BINDINGS_VERSION.major, BINDINGS_VERSION.minor))
+    }
+}
+
+/// Report the version and location of the loaded libchdb.
+///
+/// The version is detected when the first connection is opened; if none has
+/// been opened yet, an in-memory connection is opened to detect it.
+///
+/// # Errors
+///
+/// Returns an error if no connection has been opened and opening one fails.
+pub fn library_info() -> Result<LibraryInfo> {
+    let version = match VERSION.get() {
+        Some(version) => version.clone(),
+        None => {
+            let conn = Connection::open_in_memory()?;
+            detect_version(&conn)?.to_string()
+        }
+    };
+    Ok(LibraryInfo {
+        version,
+        path: library_path(),
+    })
+}
+
+/// Query and cache the libchdb version on `conn`, unless already known.
+pub(crate) fn detect_version(conn: &Connection) -> Result<&'static str> {
+    if let Some(version) = VERSION.get() {
+        return Ok(version);
+    }
+    let result = conn.query("SELECT version()", OutputFormat::TabSeparatedRaw)?;
+    let version = result.data_utf8_lossy().trim().to_string();
+    if version.is_empty() {
+        return Err(Error::InvalidData(
+            "libchdb reported an empty version".to_string(),
+        ));
+    }
+    Ok(VERSION.get_or_init(|| version))
+}
+
+/// Check the libchdb behind `conn` against the bindings.
+///
+/// Called on every [`Connection::open`]; the version is only queried once.
+pub(crate) fn check_compatibility(conn: &Connection) -> Result<()> {
+    // An undetectable version is not a reason to refuse the connection.
+    let Some(found) = detect_version(conn)
+        .ok()
+        .and_then(|v| v.parse::<Version>().ok())
+    else {
+        return Ok(());
+    };
+    if found < MIN_SUPPORTED_VERSION {
+        return Err(Error::IncompatibleLibrary {
+            found: found.to_string(),
+            required: MIN_SUPPORTED_VERSION.to_string(),
+        });
+    }
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    if (found.major, found.minor) != (BINDINGS_VERSION.major, BINDINGS_VERSION.minor) {
+        static WARNED: std::sync::Once = std::sync::Once::new();
+        WARNED.call_once(|| {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                target: "chdb",
+                "loaded libchdb {found} differs from the {BINDINGS_VERSION} the bindings \
+                 were generated from; some APIs may be unavailable"
+            );
+            #[cfg(all(feature = "log", not(feature = "tracing")))]
+            log::warn!(
+                target: "chdb",
+                "loaded libchdb {found} differs from the {BINDINGS_VERSION} the bindings \
+                 were generated from; some APIs may be unavailable"
+            );
+        });
+    }
+    Ok(())
+}
+
+/// Fail with [`Error::Unsupported`] if the loaded libchdb is older than `required`.
+///
+/// Passes when the version has not been detected, since no connection exists
+/// yet to have checked it.
+pub(crate) fn require(feature: &'static str, required: Version) -> Result<()> {
+    match VERSION.get().and_then(|v| v.parse::<Version>().ok()) {
+        Some(found) if found < required => Err(Error::Unsupported {
+            feature,
+            required: required.to_string(),
+            found: found.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(feature = "dynamic-loading")]
+fn library_path() -> Option<PathBuf> {
+    crate::dynamic::loaded_path().map(PathBuf::from)
+}
+
+#[cfg(all(unix, not(feature = "dynamic-loading")))]
+fn library_path() -> Option<PathBuf> {
+    use std::ffi::{c_char, c_int, c_void, CStr};
+    use std::os::unix::ffi::OsStrExt;
+
+    #[repr(C)]
+    struct DlInfo {
+        dli_fname: *const c_char,
+        dli_fbase: *mut c_void,
+        dli_sname: *const c_char,
+        dli_saddr: *mut c_void,
+    }
+
+    extern "C" {
+        fn dladdr(addr: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    let mut info = DlInfo {
+        dli_fname: std::ptr::null(),
+        dli_fbase: std::ptr::null_mut(),
+        dli_sname: std::ptr::null(),
+        dli_saddr: std::ptr::null_mut(),
+    };
+    let addr = crate::bindings::chdb_query as *const c_void;
+    if unsafe { dladdr(addr, &mut info) } == 0 || info.dli_fname.is_null() {
+        return None;
+    }
+    let fname = unsafe { CStr::from_ptr(info.dli_fname) };
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(fname.to_bytes())))
+}
+
+#[cfg(all(not(unix), not(feature = "dynamic-loading")))]
+fn library_path() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            "26.1.2.5".parse::<Version>().unwrap(),
+            Version::new(26, 1, 2)
+        );
+        assert_eq!("v26.1.0".parse::<Version>().unwrap(), BINDINGS_VERSION);
+        assert_eq!("25.8".parse::<Version>().unwrap(), ARROW_SCAN_MIN_VERSION);
+        assert!("26".parse::<Version>().is_err());
+        assert!("26.x".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn test_version_order() {
+        assert!(MIN_SUPPORTED_VERSION < ARROW_SCAN_MIN_VERSION);
+        assert!(ARROW_SCAN_MIN_VERSION <= BINDINGS_VERSION);
+        assert!(Version::new(26, 10, 0) > Version::new(26, 9, 3));
+    }
+
+    #[test]
+    fn test_matches_bindings() {
+        let info = |version: &str| LibraryInfo {
+            version: version.to_string(),
+            path: None,
+        };
+        assert!(info("26.1.3.1").matches_bindings());
+        assert!(!info("25.8.1.1").matches_bindings());
+        assert!(!info("unknown").matches_bindings());
+    }
+}
@@ -0,0 +1,253 @@
+//! Capture of chDB's internal log output.
+//!
+//! chDB writes its server logs to a file configured at connection time. With
+//! [`SessionBuilder::with_log_capture`](crate::session::SessionBuilder::with_log_capture),
+//! the session logs to `chdb.log` in its data directory and a background
+//! thread tails the file, re-emitting each record through the `log` crate
+//! (`log` feature) and/or as `tracing` events (`tracing` feature).
+//!
+//! Records use the `chdb` target. Levels are mapped through [`LogLevel`]:
+//! ClickHouse's `Information` becomes `info`, `Warning` becomes `warn`, and
+//! `Fatal`/`Critical` become `error`. The ClickHouse logger name, thread id
+//! and query id are kept as `logger`, `thread_id` and `query_id`: as fields
+//! of `tracing` events, and as a `logger: message` prefix for `log`.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::log_level::LogLevel;
+//! use chdb_rust::session::SessionBuilder;
+//!
+//! // Install a `log` implementation or `tracing` subscriber first.
+//! let session = SessionBuilder::new()
+//!     .with_data_path("/tmp/mydb")
+//!     .with_log_capture(LogLevel::Debug)
+//!     .build()?;
+//! session.execute("SELECT 1", None)?; // debug records appear under the `chdb` target
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, ErrorKind};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::log_level::LogLevel;
+
+/// How often the log file is checked for new records.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Name of the log file inside the session's data directory.
+pub(crate) const LOG_FILE_NAME: &str = "chdb.log";
+
+/// One parsed ClickHouse log record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Record {
+    level: LogLevel,
+    thread_id: String,
+    query_id: String,
+    logger: String,
+    message: String,
+}
+
+impl Record {
+    /// Parse a record header such as
+    /// `2024.01.02 03:04:05.123456 [ 42 ] {qid} <Debug> executeQuery: text`.
+    fn parse(line: &str) -> Option<Self> {
+        let (_, rest) = line.split_once(" [ ")?;
+        let (thread_id, rest) = rest.split_once(" ] ")?;
+        let (query_id, rest) = match rest.strip_prefix('{') {
+            Some(rest) => rest.split_once("} ")?,
+            None => ("", rest),
+        };
+        let (level, rest) = rest.strip_prefix('<')?.split_once("> ")?;
+        let level = LogLevel::from_clickhouse(level)?;
+        let (logger, message) = rest.split_once(": ").unwrap_or(("", rest));
+        Some(Self {
+            level,
+            thread_id: thread_id.trim().to_string(),
+            query_id: query_id.to_string(),
+            logger: logger.to_string(),
+            message: message.to_string(),
+        })
+    }
+
+    fn emit(&self) {
+        #[cfg(feature = "log")]
+        log::log!(target: "chdb", log::Level::from(self.level), "{}: {}", self.logger, self.message);
+
+        #[cfg(feature = "tracing")]
+        {
+            macro_rules! event {
+                ($level:expr) => {
+                    tracing::event!(
+                        target: "chdb",
+                        $level,
+                        logger = %self.logger,
+                        thread_id = %self.thread_id,
+                        query_id = %self.query_id,
+                        "{}",
+                        self.message
+                    )
+                };
+            }
+            match self.level {
+                LogLevel::Trace => event!(tracing::Level::TRACE),
+                LogLevel::Debug => event!(tracing::Level::DEBUG),
+                LogLevel::Info => event!(tracing::Level::INFO),
+                LogLevel::Warn => event!(tracing::Level::WARN),
+                LogLevel::Error => event!(tracing::Level::ERROR),
+            }
+        }
+    }
+}
+
+/// Groups log lines into records; lines without a header, such as stack
+/// traces, continue the previous record.
+#[derive(Default)]
+struct Parser {
+    pending: Option<Record>,
+}
+
+impl Parser {
+    fn push_line(&mut self, line: &str) {
+        let line = line.trim_end_matches(['\r', '\n']);
+        match Record::parse(line) {
+            Some(record) => {
+                self.flush();
+                self.pending = Some(record);
+            }
+            None => match &mut self.pending {
+                Some(record) => {
+                    record.message.push('\n');
+                    record.message.push_str(line);
+                }
+                None if !line.is_empty() => Record {
+                    level: LogLevel::Info,
+                    thread_id: String::new(),
+                    query_id: String::new(),
+                    logger: String::new(),
+                    message: line.to_string(),
+                }
+                .emit(),
+                None => {}
+            },
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(record) = self.pending.take() {
+            record.emit();
+        }
+    }
+}
+
+/// A background thread tailing chDB's log file.
+///
+/// Dropping it drains the remaining records and stops the thread.
+#[derive(Debug)]
+pub(crate) struct LogCapture {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LogCapture {
+    /// Start tailing `path`, which chDB creates when the connection opens.
+    pub(crate) fn start(path: &Path) -> Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let path = path.to_path_buf();
+        let handle = thread::Builder::new().name("chdb-log".to_string()).spawn({
+            let stop = Arc::clone(&stop);
+            move || tail(&path, &stop)
+        })?;
+        Ok(Self {
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for LogCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn tail(path: &Path, stop: &AtomicBool) {
+    let file = loop {
+        match File::open(path) {
+            Ok(file) => break file,
+            Err(e) if e.kind() == ErrorKind::NotFound && !stop.load(Ordering::Acquire) => {
+                thread::sleep(POLL_INTERVAL);
+            }
+            Err(_) => return,
+        }
+    };
+
+    let mut reader = BufReader::new(file);
+    let mut parser = Parser::default();
+    let mut line = String::new();
+    loop {
+        // Checked before reading so the final pass drains everything written so far.
+        let stopping = stop.load(Ordering::Acquire);
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                // Nothing new: the pending record is complete.
+                parser.flush();
+                if stopping {
+                    break;
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+            // A partial line is kept until the rest of it is written.
+            Ok(_) if !line.ends_with('\n') && !stopping => thread::sleep(POLL_INTERVAL),
+            Ok(_) => {
+                parser.push_line(&line);
+                line.clear();
+            }
+        }
+    }
+    if !line.is_empty() {
+        parser.push_line(&line);
+    }
+    parser.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_record() {
+        let record = Record::parse(
+            "2024.01.02 03:04:05.123456 [ 4242 ] {abc-1} <Information> executeQuery: Read 1 rows: x",
+        )
+        .unwrap();
+        assert_eq!(record.level, LogLevel::Info);
+        assert_eq!(record.thread_id, "4242");
+        assert_eq!(record.query_id, "abc-1");
+        assert_eq!(record.logger, "executeQuery");
+        assert_eq!(record.message, "Read 1 rows: x");
+
+        let record = Record::parse("2024.01.02 03:04:05.1 [ 7 ] <Fatal> BaseDaemon: boom").unwrap();
+        assert_eq!(record.level, LogLevel::Error);
+        assert_eq!(record.query_id, "");
+
+        assert!(Record::parse("0. DB::Exception::Exception() @ 0x1234").is_none());
+    }
+
+    #[test]
+    fn test_continuation_lines() {
+        let mut parser = Parser::default();
+        parser.push_line("2024.01.02 03:04:05.1 [ 7 ] <Error> X: failed\n");
+        parser.push_line("0. frame\n");
+        assert_eq!(parser.pending.as_ref().unwrap().message, "failed\n0. frame");
+    }
+}
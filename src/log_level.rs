@@ -6,7 +6,7 @@
 ///
 /// These correspond to the standard log levels used by chDB for controlling
 /// the verbosity of log output.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LogLevel {
     Trace,
     Debug,
@@ -32,4 +32,45 @@ impl LogLevel {
             Self::Error => "error",
         }
     }
+
+    /// Map a ClickHouse log level name, as written in its log files, to a `LogLevel`.
+    ///
+    /// `Test` maps to `Trace`, `Notice` to `Info`, and `Critical` and `Fatal`
+    /// to `Error`.
+    pub fn from_clickhouse(name: &str) -> Option<Self> {
+        Some(match name {
+            "Test" | "Trace" => Self::Trace,
+            "Debug" => Self::Debug,
+            "Information" | "Notice" => Self::Info,
+            "Warning" => Self::Warn,
+            "Error" | "Critical" | "Fatal" => Self::Error,
+            _ => return None,
+        })
+    }
+}
+
+#[cfg(feature = "log")]
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => Self::Trace,
+            LogLevel::Debug => Self::Debug,
+            LogLevel::Info => Self::Info,
+            LogLevel::Warn => Self::Warn,
+            LogLevel::Error => Self::Error,
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl From<LogLevel> for tracing::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Trace => Self::TRACE,
+            LogLevel::Debug => Self::DEBUG,
+            LogLevel::Info => Self::INFO,
+            LogLevel::Warn => Self::WARN,
+            LogLevel::Error => Self::ERROR,
+        }
+    }
 }
@@ -0,0 +1,384 @@
+//! Versioned schema migrations for persistent sessions.
+//!
+//! This module provides a [`Migrator`] that applies an ordered list of SQL
+//! [`Migration`]s to a [`Session`] and records each applied version in a
+//! bookkeeping table. On every run the checksums of already-applied
+//! migrations are compared against the local definitions, so an edited
+//! migration is detected instead of silently diverging.
+//!
+//! Migrations can be embedded in the binary with [`Migration::new`] and
+//! `include_str!`, or loaded at runtime from a directory of files named
+//! `<version>_<name>.sql` (for example `0001_create_events.sql`).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::migrations::{Migration, Migrator};
+//! use chdb_rust::session::SessionBuilder;
+//!
+//! let session = SessionBuilder::new()
+//!     .with_data_path("/var/lib/myapp/chdb")
+//!     .build()?;
+//!
+//! let migrator = Migrator::new(vec![
+//!     Migration::new(
+//!         1,
+//!         "create_events",
+//!         "CREATE TABLE events (id UInt64, kind String) ENGINE = MergeTree() ORDER BY id",
+//!     ),
+//!     Migration::new(2, "add_ts", "ALTER TABLE events ADD COLUMN ts DateTime DEFAULT now()"),
+//! ])?;
+//!
+//! let report = migrator.run(&session)?;
+//! println!("Applied versions: {:?}", report.applied);
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use crate::arg::Arg;
+use crate::error::{Error, Result};
+use crate::format::OutputFormat;
+use crate::hash::fnv1a;
+use crate::query_builder::{quote_identifier, split_statements, Insert, Value};
+use crate::session::Session;
+
+/// Default name of the bookkeeping table.
+pub const DEFAULT_MIGRATIONS_TABLE: &str = "default._chdb_migrations";
+
+/// A single versioned migration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    version: u64,
+    name: String,
+    sql: String,
+}
+
+impl Migration {
+    /// Create a migration from its version, a descriptive name and its SQL.
+    ///
+    /// The SQL may contain several statements separated by `;`, which are
+    /// run one at a time.
+    pub fn new(version: u64, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+
+    /// The migration version.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// The migration name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The migration SQL.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// The statements of the migration SQL.
+    fn statements(&self) -> Vec<&str> {
+        split_statements(&self.sql)
+    }
+
+    /// Checksum of the migration SQL, as stored in the bookkeeping table.
+    ///
+    /// This is the 64-bit FNV-1a hash of the SQL text, rendered as hex.
+    pub fn checksum(&self) -> String {
+        format!("{:016x}", fnv1a(self.sql.as_bytes()))
+    }
+
+    /// Parse a migration file name of the form `<version>_<name>.sql`.
+    fn parse_file_name(file_name: &str) -> Option<(u64, String)> {
+        let stem = file_name.strip_suffix(".sql")?;
+        let digits = stem
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(stem.len());
+        let version = stem[..digits].parse().ok()?;
+        let name = stem[digits..].trim_start_matches('_');
+        Some((version, name.to_string()))
+    }
+}
+
+/// A migration recorded in the bookkeeping table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppliedMigration {
+    /// The migration version.
+    pub version: u64,
+    /// The migration name at the time it was applied.
+    pub name: String,
+    /// The checksum at the time it was applied.
+    pub checksum: String,
+}
+
+/// Outcome of [`Migrator::run`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// Versions that were already applied before this run.
+    pub already_applied: Vec<u64>,
+    /// Versions applied by this run, or that would be applied in dry-run mode.
+    pub applied: Vec<u64>,
+    /// Whether this was a dry run.
+    pub dry_run: bool,
+}
+
+/// Applies [`Migration`]s to a session in version order.
+#[derive(Debug, Clone)]
+pub struct Migrator {
+    migrations: Vec<Migration>,
+    table: String,
+    dry_run: bool,
+}
+
+impl Migrator {
+    /// Create a migrator from a list of migrations.
+    ///
+    /// Migrations are sorted by version; their input order does not matter.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] if two migrations share a version.
+    pub fn new(mut migrations: Vec<Migration>) -> Result<Self> {
+        migrations.sort_by_key(|m| m.version);
+        if let Some(w) = migrations.windows(2).find(|w| w[0].version == w[1].version) {
+            return Err(Error::InvalidData(format!(
+                "Duplicate migration version {}",
+                w[0].version
+            )));
+        }
+
+        Ok(Self {
+            migrations,
+            table: DEFAULT_MIGRATIONS_TABLE.to_string(),
+            dry_run: false,
+        })
+    }
+
+    /// Load migrations from `*.sql` files in a directory.
+    ///
+    /// Files must be named `<version>_<name>.sql`; other files are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or two files share
+    /// a version.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let mut migrations = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if let Some((version, name)) = Migration::parse_file_name(file_name) {
+                migrations.push(Migration::new(version, name, fs::read_to_string(&path)?));
+            }
+        }
+        Self::new(migrations)
+    }
+
+    /// Set the bookkeeping table. Defaults to [`DEFAULT_MIGRATIONS_TABLE`].
+    pub fn with_table(mut self, table: impl Into<String>) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// Only report pending migrations instead of applying them.
+    pub fn dry_run(mut self, value: bool) -> Self {
+        self.dry_run = value;
+        self
+    }
+
+    /// The migrations known to this migrator, in version order.
+    pub fn migrations(&self) -> &[Migration] {
+        &self.migrations
+    }
+
+    /// Read the migrations recorded in the bookkeeping table.
+    ///
+    /// Returns an empty list if the table does not exist yet.
+    pub fn applied(&self, session: &Session) -> Result<Vec<AppliedMigration>> {
+        let table = quote_identifier(&self.table);
+        let exists = session.execute(
+            &format!("EXISTS TABLE {table}"),
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparated)]),
+        )?;
+        if exists.data_utf8_lossy().trim() != "1" {
+            return Ok(Vec::new());
+        }
+
+        let result = session.execute(
+            &format!("SELECT version, name, checksum FROM {table} ORDER BY version"),
+            Some(&[Arg::OutputFormat(OutputFormat::TabSeparatedRaw)]),
+        )?;
+        result
+            .data_utf8_lossy()
+            .lines()
+            .map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let (Some(version), Some(name), Some(checksum)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(Error::InvalidData(format!(
+                        "Malformed row in migrations table: {line}"
+                    )));
+                };
+                Ok(AppliedMigration {
+                    version: version
+                        .parse()
+                        .map_err(|_| Error::InvalidData(format!("Invalid version: {version}")))?,
+                    name: name.to_string(),
+                    checksum: checksum.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Apply all pending migrations.
+    ///
+    /// Already-applied migrations are verified first; nothing is applied if
+    /// any of them fails verification. Pending migrations are applied in
+    /// version order, and each one is recorded right after it succeeds, so a
+    /// failing migration leaves earlier ones recorded. ClickHouse DDL is not
+    /// transactional: if a statement of a migration fails, the statements
+    /// before it stay applied while the migration is not recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::MigrationChecksumMismatch`] if an applied migration was
+    /// modified, [`Error::UnknownMigration`] if the database records a version
+    /// this migrator does not know, or the query error of a failing migration.
+    pub fn run(&self, session: &Session) -> Result<MigrationReport> {
+        let applied = self.applied(session)?;
+
+        for record in &applied {
+            let migration = self
+                .migrations
+                .iter()
+                .find(|m| m.version == record.version)
+                .ok_or(Error::UnknownMigration(record.version))?;
+            if migration.checksum() != record.checksum {
+                return Err(Error::MigrationChecksumMismatch {
+                    version: record.version,
+                    name: migration.name.clone(),
+                });
+            }
+        }
+
+        let mut report = MigrationReport {
+            already_applied: applied.iter().map(|r| r.version).collect(),
+            applied: Vec::new(),
+            dry_run: self.dry_run,
+        };
+        let pending = self
+            .migrations
+            .iter()
+            .filter(|m| !report.already_applied.contains(&m.version));
+
+        if self.dry_run {
+            report.applied = pending.map(|m| m.version).collect();
+            return Ok(report);
+        }
+
+        self.ensure_table(session)?;
+        for migration in pending {
+            for statement in migration.statements() {
+                session.execute(statement, None)?;
+            }
+            Insert::into(self.table.as_str())
+                .columns(["version", "name", "checksum"])
+                .row([
+                    Value::from(migration.version),
+                    migration.name.as_str().into(),
+                    migration.checksum().into(),
+                ])
+                .execute(session, None)?;
+            report.applied.push(migration.version);
+        }
+        Ok(report)
+    }
+
+    fn ensure_table(&self, session: &Session) -> Result<()> {
+        session.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {} (\
+                 version UInt64, name String, checksum String, applied_at DateTime DEFAULT now()\
+                 ) ENGINE = MergeTree() ORDER BY version",
+                quote_identifier(&self.table)
+            ),
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file_name() {
+        assert_eq!(
+            Migration::parse_file_name("0001_create_events.sql"),
+            Some((1, "create_events".to_string()))
+        );
+        assert_eq!(
+            Migration::parse_file_name("20240101__init.sql"),
+            Some((20240101, "init".to_string()))
+        );
+        assert_eq!(Migration::parse_file_name("README.md"), None);
+        assert_eq!(Migration::parse_file_name("init.sql"), None);
+    }
+
+    #[test]
+    fn test_statements() {
+        let migration = Migration::new(
+            1,
+            "init",
+            "CREATE TABLE a (id UInt64) ENGINE = Memory;\n\
+             -- b holds ';'-separated values\n\
+             CREATE TABLE b (v String DEFAULT ';') ENGINE = Memory;\n",
+        );
+        assert_eq!(
+            migration.statements(),
+            [
+                "CREATE TABLE a (id UInt64) ENGINE = Memory",
+                "-- b holds ';'-separated values\n\
+                 CREATE TABLE b (v String DEFAULT ';') ENGINE = Memory",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checksum_is_stable() {
+        let a = Migration::new(1, "a", "SELECT 1");
+        let b = Migration::new(2, "b", "SELECT 1");
+        let c = Migration::new(1, "a", "SELECT 2");
+        assert_eq!(a.checksum(), b.checksum());
+        assert_ne!(a.checksum(), c.checksum());
+        assert_eq!(a.checksum().len(), 16);
+    }
+
+    #[test]
+    fn test_duplicate_versions_rejected() {
+        let result = Migrator::new(vec![
+            Migration::new(1, "a", "SELECT 1"),
+            Migration::new(1, "b", "SELECT 2"),
+        ]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_migrations_sorted() {
+        let migrator =
+            Migrator::new(vec![Migration::new(3, "c", ""), Migration::new(1, "a", "")]).unwrap();
+        let versions: Vec<_> = migrator.migrations().iter().map(|m| m.version()).collect();
+        assert_eq!(versions, vec![1, 3]);
+    }
+}
@@ -0,0 +1,360 @@
+//! Bounded concurrent access to a single chDB connection.
+//!
+//! `chdb_connect` supports only one connection per process, so unlike
+//! general-purpose pools, [`Pool`] never opens connections: it owns the one
+//! [`Connection`] and hands out [`QueryContext`]s over it. At most
+//! `max_in_flight` contexts exist at a time; [`Pool::get`] waits up to
+//! `max_wait` for one to be returned and fails with [`Error::PoolTimeout`]
+//! otherwise.
+//!
+//! Contexts only run queries through `chdb_query`, which chDB documents as
+//! thread-safe, so several can run at once. Each query is isolated from the
+//! others: settings travel with the query, through
+//! [`QueryContext::query_with_options`] or a `SETTINGS` clause, and `SET` and
+//! `USE` statements, which would change the connection for every context,
+//! are rejected. Streaming, Arrow registration and other connection-level
+//! APIs are not available through a pool.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::thread;
+//! use std::time::Duration;
+//!
+//! use chdb_rust::connection::Connection;
+//! use chdb_rust::format::OutputFormat;
+//! use chdb_rust::pool::PoolBuilder;
+//!
+//! let pool = PoolBuilder::new()
+//!     .with_max_in_flight(4)
+//!     .with_max_wait(Duration::from_secs(5))
+//!     .build(Connection::open_in_memory()?);
+//!
+//! let handles: Vec<_> = (0..16)
+//!     .map(|i| {
+//!         let pool = pool.clone();
+//!         thread::spawn(move || -> chdb_rust::error::Result<String> {
+//!             let ctx = pool.get()?;
+//!             let sql = format!("SELECT sum(number) FROM numbers({i} * 1000000)");
+//!             Ok(ctx.query(&sql, OutputFormat::CSV)?.data_utf8_lossy().into_owned())
+//!         })
+//!     })
+//!     .collect();
+//! for handle in handles {
+//!     println!("{}", handle.join().unwrap()?);
+//! }
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::format::{FormatOptions, OutputFormat};
+use crate::query_result::QueryResult;
+use crate::session::leading_keyword;
+
+/// Default limit on concurrent queries: the number of available CPUs.
+fn default_max_in_flight() -> usize {
+    std::thread::available_parallelism().map_or(4, |n| n.get())
+}
+
+/// Default time [`Pool::get`] waits for a free slot.
+const DEFAULT_MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// Builder for creating [`Pool`] instances.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use chdb_rust::connection::Connection;
+/// use chdb_rust::pool::PoolBuilder;
+///
+/// let pool = PoolBuilder::new()
+///     .with_max_in_flight(8)
+///     .with_max_wait(Duration::from_millis(500))
+///     .build(Connection::open_in_memory()?);
+/// # Ok::<(), chdb_rust::error::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct PoolBuilder {
+    max_in_flight: usize,
+    max_wait: Duration,
+}
+
+impl PoolBuilder {
+    /// Create a new `PoolBuilder` with default settings.
+    ///
+    /// The default settings are:
+    /// - Max in flight: the number of available CPUs
+    /// - Max wait: 30 seconds
+    pub fn new() -> Self {
+        Self {
+            max_in_flight: default_max_in_flight(),
+            max_wait: DEFAULT_MAX_WAIT,
+        }
+    }
+
+    /// Set the maximum number of queries running at once.
+    ///
+    /// Values below 1 are treated as 1.
+    pub fn with_max_in_flight(mut self, max_in_flight: usize) -> Self {
+        self.max_in_flight = max_in_flight.max(1);
+        self
+    }
+
+    /// Set how long [`Pool::get`] waits for a free slot before failing.
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    /// Build a pool over `conn`, which it takes ownership of.
+    pub fn build(self, conn: Connection) -> Pool {
+        Pool {
+            inner: Arc::new(Inner {
+                conn,
+                slots: Slots::new(self.max_in_flight),
+                max_wait: self.max_wait,
+            }),
+        }
+    }
+}
+
+impl Default for PoolBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A pool of [`QueryContext`]s sharing one [`Connection`].
+///
+/// Cloning a pool is cheap and yields a handle to the same pool, so it can
+/// be shared across threads. The connection is closed when the last handle
+/// and context are dropped.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    conn: Connection,
+    slots: Slots,
+    max_wait: Duration,
+}
+
+// Safety: the connection is only used through `QueryContext`, whose methods
+// all call `chdb_query`, documented in chdb.h as "Thread-safe function that
+// handles query execution in a separate thread".
+unsafe impl Sync for Inner {}
+
+impl Pool {
+    /// Get a context, waiting up to the configured max wait for a free slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PoolTimeout`] if no slot frees up in time.
+    pub fn get(&self) -> Result<QueryContext> {
+        self.get_timeout(self.inner.max_wait)
+    }
+
+    /// Get a context, waiting up to `timeout` for a free slot.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::PoolTimeout`] if no slot frees up in time.
+    pub fn get_timeout(&self, timeout: Duration) -> Result<QueryContext> {
+        if !self.inner.slots.acquire(timeout) {
+            return Err(Error::PoolTimeout(timeout));
+        }
+        Ok(QueryContext {
+            inner: Arc::clone(&self.inner),
+        })
+    }
+
+    /// Get a context if a slot is free, without waiting.
+    pub fn try_get(&self) -> Option<QueryContext> {
+        self.get_timeout(Duration::ZERO).ok()
+    }
+
+    /// The number of contexts currently handed out.
+    pub fn in_flight(&self) -> usize {
+        self.inner.slots.in_flight()
+    }
+
+    /// The maximum number of contexts handed out at once.
+    pub fn max_in_flight(&self) -> usize {
+        self.inner.slots.max
+    }
+}
+
+/// A slot in a [`Pool`], running queries on the shared [`Connection`].
+///
+/// The slot is returned to the pool when the context is dropped.
+#[derive(Debug)]
+pub struct QueryContext {
+    inner: Arc<Inner>,
+}
+
+impl QueryContext {
+    /// Execute a query and return the result.
+    ///
+    /// See [`Connection::query`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] for `SET` and `USE` statements, or an
+    /// error if the query fails.
+    pub fn query(&self, sql: &str, format: OutputFormat) -> Result<QueryResult> {
+        check_isolated(sql)?;
+        self.inner.conn.query(sql, format)
+    }
+
+    /// Execute a query with per-query [`FormatOptions`] or other settings.
+    ///
+    /// See [`Connection::query_with_options`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] for `SET` and `USE` statements, or an
+    /// error if the query fails.
+    pub fn query_with_options(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        options: &FormatOptions,
+    ) -> Result<QueryResult> {
+        check_isolated(sql)?;
+        self.inner.conn.query_with_options(sql, format, options)
+    }
+
+    /// Execute a query tagged with a query id.
+    ///
+    /// See [`Connection::query_with_id`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] for `SET` and `USE` statements, or an
+    /// error if the query fails.
+    pub fn query_with_id(
+        &self,
+        sql: &str,
+        format: OutputFormat,
+        query_id: Option<&str>,
+    ) -> Result<QueryResult> {
+        check_isolated(sql)?;
+        self.inner.conn.query_with_id(sql, format, query_id)
+    }
+}
+
+/// Reject statements that would change the connection for other contexts.
+fn check_isolated(sql: &str) -> Result<()> {
+    let keyword = leading_keyword(sql);
+    if matches!(keyword.as_str(), "SET" | "USE") {
+        return Err(Error::InvalidData(format!(
+            "{keyword} statements would affect every pooled query; \
+             pass settings per query or qualify table names instead"
+        )));
+    }
+    Ok(())
+}
+
+impl Drop for QueryContext {
+    fn drop(&mut self) {
+        self.inner.slots.release();
+    }
+}
+
+/// A counting semaphore limiting the contexts handed out.
+#[derive(Debug)]
+struct Slots {
+    max: usize,
+    in_flight: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Slots {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            in_flight: Mutex::new(0),
+            released: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, usize> {
+        // The counter stays consistent even if a holder panicked.
+        self.in_flight.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take a slot, waiting up to `timeout`. Returns whether one was taken.
+    fn acquire(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut in_flight = self.lock();
+        while *in_flight >= self.max {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            in_flight = self
+                .released
+                .wait_timeout(in_flight, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+        *in_flight += 1;
+        true
+    }
+
+    fn release(&self) {
+        *self.lock() -= 1;
+        self.released.notify_one();
+    }
+
+    fn in_flight(&self) -> usize {
+        *self.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_check_isolated() {
+        assert!(check_isolated("SELECT 1 SETTINGS max_threads = 1").is_ok());
+        assert!(check_isolated("SYSTEM FLUSH LOGS").is_ok());
+        assert!(check_isolated("set max_threads = 1").is_err());
+        assert!(check_isolated("/* db */ USE analytics").is_err());
+    }
+
+    #[test]
+    fn test_slots_limit() {
+        let slots = Slots::new(2);
+        assert!(slots.acquire(Duration::ZERO));
+        assert!(slots.acquire(Duration::ZERO));
+        assert!(!slots.acquire(Duration::from_millis(10)));
+        assert_eq!(slots.in_flight(), 2);
+        slots.release();
+        assert!(slots.acquire(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_slots_wait_for_release() {
+        let slots = Arc::new(Slots::new(1));
+        assert!(slots.acquire(Duration::ZERO));
+        let waiter = {
+            let slots = Arc::clone(&slots);
+            thread::spawn(move || slots.acquire(Duration::from_secs(10)))
+        };
+        thread::sleep(Duration::from_millis(20));
+        slots.release();
+        assert!(waiter.join().unwrap());
+        assert_eq!(slots.in_flight(), 1);
+    }
+}
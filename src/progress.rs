@@ -0,0 +1,187 @@
+//! Progress reporting for long-running queries.
+//!
+//! [`Connection::query_with_progress`] streams a query and reports a
+//! [`Progress`] as it runs. With a `*WithProgress` output format such as
+//! [`OutputFormat::JSONEachRowWithProgress`], progress comes from the
+//! progress rows ClickHouse interleaves with the data, which include the
+//! total number of rows to read; those rows are removed from the output.
+//! With any other format, progress is reported after each streamed chunk,
+//! and the total is unknown.
+//!
+//! [`Connection::query_with_progress`]: crate::connection::Connection::query_with_progress
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::connection::Connection;
+//! use chdb_rust::format::OutputFormat;
+//!
+//! let conn = Connection::open_in_memory()?;
+//! let output = conn.query_with_progress(
+//!     "SELECT count() FROM numbers(100000000) WHERE number % 7 = 0",
+//!     OutputFormat::JSONEachRowWithProgress,
+//!     |progress| match progress.fraction() {
+//!         Some(done) => eprint!("\r{:5.1}%", done * 100.0),
+//!         None => eprint!("\r{} rows", progress.read_rows),
+//!     },
+//! )?;
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::time::Duration;
+
+use crate::format::OutputFormat;
+use crate::json::Json;
+use crate::query_result::QueryResult;
+
+/// The progress of a running query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Rows read so far.
+    pub read_rows: u64,
+    /// Bytes read so far.
+    pub read_bytes: u64,
+    /// The estimated total number of rows to read, if known.
+    pub total_rows_to_read: Option<u64>,
+    /// Time since the query started.
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// The fraction of rows read, between 0 and 1, if the total is known.
+    pub fn fraction(&self) -> Option<f64> {
+        self.total_rows_to_read
+            .filter(|&total| total > 0)
+            .map(|total| (self.read_rows as f64 / total as f64).min(1.0))
+    }
+
+    /// Parse a progress row such as
+    /// `{"progress":{"read_rows":"8192","read_bytes":"65536","total_rows_to_read":"100000",...}}`.
+    fn parse_row(line: &str) -> Option<Self> {
+        // Skip parsing the data rows.
+        if !line.trim_start().starts_with("{\"progress\":") {
+            return None;
+        }
+        let json = Json::parse(line).ok()?;
+        let body = json.get("progress")?;
+        let field = |key| body.get(key).and_then(Json::as_u64);
+        Some(Self {
+            read_rows: field("read_rows")?,
+            read_bytes: field("read_bytes").unwrap_or(0),
+            total_rows_to_read: field("total_rows_to_read").filter(|&n| n > 0),
+            elapsed: Duration::from_nanos(field("elapsed_ns").unwrap_or(0)),
+        })
+    }
+}
+
+/// Turns streamed chunks into output and progress updates.
+pub(crate) struct ProgressTracker {
+    progress_rows: bool,
+    progress: Progress,
+    /// An incomplete trailing line, when progress rows are parsed.
+    partial: Vec<u8>,
+    output: Vec<u8>,
+}
+
+impl ProgressTracker {
+    pub(crate) fn new(format: OutputFormat) -> Self {
+        Self {
+            progress_rows: format.has_progress_rows(),
+            progress: Progress::default(),
+            partial: Vec::new(),
+            output: Vec::new(),
+        }
+    }
+
+    /// Consume one chunk, calling `on_progress` for every update.
+    pub(crate) fn chunk(
+        &mut self,
+        chunk: &QueryResult,
+        elapsed: Duration,
+        on_progress: &mut impl FnMut(Progress),
+    ) {
+        if !self.progress_rows {
+            self.output.extend_from_slice(chunk.data_ref());
+            self.progress.read_rows += chunk.rows_read();
+            self.progress.read_bytes += chunk.bytes_read();
+            self.progress.elapsed = elapsed;
+            on_progress(self.progress);
+            return;
+        }
+
+        self.partial.extend_from_slice(chunk.data_ref());
+        let Some(end) = self.partial.iter().rposition(|&b| b == b'\n') else {
+            return;
+        };
+        let lines: Vec<u8> = self.partial.drain(..=end).collect();
+        for line in lines.split_inclusive(|&b| b == b'\n') {
+            self.line(line, elapsed, on_progress);
+        }
+    }
+
+    /// Flush the remaining output.
+    pub(crate) fn finish(
+        mut self,
+        elapsed: Duration,
+        on_progress: &mut impl FnMut(Progress),
+    ) -> Vec<u8> {
+        let partial = std::mem::take(&mut self.partial);
+        if !partial.is_empty() {
+            self.line(&partial, elapsed, on_progress);
+        }
+        self.output
+    }
+
+    fn line(&mut self, line: &[u8], elapsed: Duration, on_progress: &mut impl FnMut(Progress)) {
+        let parsed = std::str::from_utf8(line).ok().and_then(Progress::parse_row);
+        let Some(row) = parsed else {
+            self.output.extend_from_slice(line);
+            return;
+        };
+        // Progress rows are cumulative; keep the reported values monotonic.
+        self.progress = Progress {
+            read_rows: row.read_rows.max(self.progress.read_rows),
+            read_bytes: row.read_bytes.max(self.progress.read_bytes),
+            total_rows_to_read: row.total_rows_to_read.or(self.progress.total_rows_to_read),
+            elapsed: if row.elapsed.is_zero() {
+                elapsed
+            } else {
+                row.elapsed
+            },
+        };
+        on_progress(self.progress);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_row() {
+        let progress = Progress::parse_row(
+            r#"{"progress":{"read_rows":"8192","read_bytes":"65536","written_rows":"0","written_bytes":"0","total_rows_to_read":"32768","result_rows":"0","result_bytes":"0","elapsed_ns":"1500000"}}"#,
+        )
+        .unwrap();
+        assert_eq!(progress.read_rows, 8192);
+        assert_eq!(progress.read_bytes, 65536);
+        assert_eq!(progress.total_rows_to_read, Some(32768));
+        assert_eq!(progress.elapsed, Duration::from_micros(1500));
+        assert_eq!(progress.fraction(), Some(0.25));
+
+        let progress =
+            Progress::parse_row(r#"{"progress":{"read_rows":10,"total_rows_to_read":0}}"#).unwrap();
+        assert_eq!(progress.read_rows, 10);
+        assert_eq!(progress.total_rows_to_read, None);
+        assert_eq!(progress.fraction(), None);
+
+        assert!(Progress::parse_row(r#"{"row":{"read_rows":"1"}}"#).is_none());
+        // Keys inside nested objects or string values are not fields.
+        let progress = Progress::parse_row(
+            r#"{"progress":{"meta":{"read_rows":"7"},"note":"\"read_bytes\":9","read_rows":"3"}}"#,
+        )
+        .unwrap();
+        assert_eq!(progress.read_rows, 3);
+        assert_eq!(progress.read_bytes, 0);
+    }
+}
@@ -0,0 +1,1135 @@
+//! Fluent SQL builders for common chDB statements.
+//!
+//! This module provides lightweight builders for `SELECT`, `INSERT` and
+//! `CREATE TABLE` statements. Table and column names are always quoted and
+//! values are bound through [`Value`], which renders them as escaped SQL
+//! literals, so callers never have to concatenate user input into SQL by
+//! hand. Expressions, such as filters or sorting keys, are written verbatim.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::query_builder::{CreateTable, Insert, Order, Select, Value};
+//! use chdb_rust::session::SessionBuilder;
+//!
+//! let session = SessionBuilder::new()
+//!     .with_data_path("/tmp/mydb")
+//!     .with_auto_cleanup(true)
+//!     .build()?;
+//!
+//! CreateTable::new("events")
+//!     .if_not_exists()
+//!     .column("id", "UInt64")
+//!     .column("kind", "LowCardinality(String)")
+//!     .engine("MergeTree()")
+//!     .order_by(["id"])
+//!     .execute(&session, None)?;
+//!
+//! Insert::into("events")
+//!     .columns(["id", "kind"])
+//!     .row([Value::from(1), "click".into()])
+//!     .execute(&session, None)?;
+//!
+//! let result = Select::from("events")
+//!     .columns(["kind"])
+//!     .expr("count() AS n")
+//!     .filter("id > ?", [0])
+//!     .group_by(["kind"])
+//!     .order_by("kind", Order::Asc)
+//!     .limit(10)
+//!     .execute(&session, None)?;
+//! println!("{}", result.data_utf8_lossy());
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fmt::Write as _;
+use std::iter::Peekable;
+
+use crate::arg::Arg;
+use crate::error::{Error, Result};
+use crate::query_result::QueryResult;
+use crate::row::Row;
+use crate::session::{leading_keyword, Session};
+use crate::table_function::TableFunction;
+
+/// A value bound into a generated statement.
+///
+/// Values are rendered as SQL literals with all special characters escaped,
+/// so they are safe to use with untrusted input.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// `NULL`
+    Null,
+    /// `true` / `false`
+    Bool(bool),
+    /// A signed integer.
+    Int(i64),
+    /// An unsigned integer.
+    UInt(u64),
+    /// A floating point number.
+    Float(f64),
+    /// A string, rendered as a single-quoted literal.
+    String(String),
+    /// An array of values, rendered as `[a, b, ...]`.
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Render the value as a SQL literal.
+    pub fn to_sql(&self) -> String {
+        let mut out = String::new();
+        self.write_sql(&mut out);
+        out
+    }
+
+    fn write_sql(&self, out: &mut String) {
+        match self {
+            Self::Null => out.push_str("NULL"),
+            Self::Bool(v) => out.push_str(if *v { "true" } else { "false" }),
+            Self::Int(v) => {
+                let _ = write!(out, "{v}");
+            }
+            Self::UInt(v) => {
+                let _ = write!(out, "{v}");
+            }
+            Self::Float(v) if v.is_nan() => out.push_str("nan"),
+            Self::Float(v) if v.is_infinite() => {
+                out.push_str(if *v > 0.0 { "inf" } else { "-inf" })
+            }
+            Self::Float(v) => {
+                let _ = write!(out, "{v:?}");
+            }
+            Self::String(v) => out.push_str(&quote_literal(v)),
+            Self::Array(values) => {
+                out.push('[');
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push_str(", ");
+                    }
+                    v.write_sql(out);
+                }
+                out.push(']');
+            }
+        }
+    }
+}
+
+macro_rules! impl_value_from {
+    ($variant:ident($target:ty): $($t:ty),*) => {
+        $(
+            impl From<$t> for Value {
+                fn from(v: $t) -> Self {
+                    Self::$variant(<$target>::from(v))
+                }
+            }
+        )*
+    };
+}
+
+impl_value_from!(Bool(bool): bool);
+impl_value_from!(Int(i64): i8, i16, i32, i64);
+impl_value_from!(UInt(u64): u8, u16, u32, u64);
+impl_value_from!(Float(f64): f32, f64);
+impl_value_from!(String(String): &str, String, &String);
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Self::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(v: Vec<T>) -> Self {
+        Self::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Quote a table or database name with backticks.
+///
+/// Dotted names such as `db.table` are split and each part is quoted
+/// separately. Backticks and backslashes inside the name are escaped. Use
+/// [`quote_column`] for column names, which may contain dots.
+///
+/// # Examples
+///
+/// ```
+/// use chdb_rust::query_builder::quote_identifier;
+///
+/// assert_eq!(quote_identifier("events"), "`events`");
+/// assert_eq!(quote_identifier("db.events"), "`db`.`events`");
+/// ```
+pub fn quote_identifier(name: &str) -> String {
+    name.split('.')
+        .map(quote_column)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Quote a column name with backticks, as a single identifier.
+///
+/// Unlike [`quote_identifier`], dots are kept inside the quotes, so that
+/// `Nested` subcolumns such as `n.x` stay one name. Backticks and
+/// backslashes inside the name are escaped.
+///
+/// # Examples
+///
+/// ```
+/// use chdb_rust::query_builder::quote_column;
+///
+/// assert_eq!(quote_column("id"), "`id`");
+/// assert_eq!(quote_column("attrs.key"), "`attrs.key`");
+/// ```
+pub fn quote_column(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 2);
+    out.push('`');
+    for c in name.chars() {
+        if c == '`' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('`');
+    out
+}
+
+/// Quote a string as a single-quoted SQL literal.
+///
+/// # Examples
+///
+/// ```
+/// use chdb_rust::query_builder::quote_literal;
+///
+/// assert_eq!(quote_literal("it's"), "'it\\'s'");
+/// ```
+pub fn quote_literal(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for c in value.chars() {
+        match c {
+            '\'' => out.push_str("\\'"),
+            '\\' => out.push_str("\\\\"),
+            '\0' => out.push_str("\\0"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Quote raw bytes as a single-quoted SQL string literal.
+///
+/// Printable ASCII is kept as-is; every other byte is written as a `\xHH`
+/// escape, so arbitrary binary data (e.g. `RowBinary` payloads) survives.
+pub(crate) fn quote_bytes(value: &[u8]) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('\'');
+    for &b in value {
+        match b {
+            b'\'' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            0x20..=0x7e => out.push(b as char),
+            _ => {
+                let _ = write!(out, "\\x{b:02x}");
+            }
+        }
+    }
+    out.push('\'');
+    out
+}
+
+/// Substitute `?` placeholders in `expr` with the rendered `params`.
+///
+/// Placeholders inside quoted strings, identifiers or comments are left
+/// untouched.
+fn bind_params(expr: &str, params: &[Value]) -> Result<String> {
+    let mut out = String::with_capacity(expr.len());
+    let mut params = params.iter();
+    let mut chars = expr.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => skip_quoted(&mut chars, c),
+            _ if skip_comment(&mut chars, c) => {}
+            '?' => {
+                let value = params.next().ok_or_else(|| {
+                    Error::InvalidData(format!("Missing parameter for placeholder in '{expr}'"))
+                })?;
+                value.write_sql(&mut out);
+                continue;
+            }
+            _ => {}
+        }
+        // Copy everything consumed since `i` verbatim.
+        let end = chars.peek().map_or(expr.len(), |&(next, _)| next);
+        out.push_str(&expr[i..end]);
+    }
+
+    if params.next().is_some() {
+        return Err(Error::InvalidData(format!(
+            "Too many parameters bound to '{expr}'"
+        )));
+    }
+    Ok(out)
+}
+
+/// Split `sql` into its statements at the `;`s outside of quotes and
+/// comments.
+///
+/// Statements are trimmed, and pieces holding only whitespace and comments
+/// are dropped, so a trailing `;` or comment does not count as a statement.
+pub(crate) fn split_statements(sql: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                has_code = true;
+                skip_quoted(&mut chars, c);
+            }
+            _ if skip_comment(&mut chars, c) => {}
+            ';' => {
+                if has_code {
+                    statements.push(sql[start..i].trim());
+                }
+                start = i + 1;
+                has_code = false;
+            }
+            c if c.is_whitespace() => {}
+            _ => has_code = true,
+        }
+    }
+    if has_code {
+        statements.push(sql[start..].trim());
+    }
+    statements
+}
+
+type CharIndices<'a> = Peekable<std::str::CharIndices<'a>>;
+
+/// Consume the rest of a string or identifier opened by `quote`.
+fn skip_quoted(chars: &mut CharIndices<'_>, quote: char) {
+    while let Some((_, next)) = chars.next() {
+        if next == '\\' {
+            chars.next();
+        } else if next == quote {
+            break;
+        }
+    }
+}
+
+/// Consume the rest of a comment if `c` opens one, returning whether it did.
+fn skip_comment(chars: &mut CharIndices<'_>, c: char) -> bool {
+    match (c, chars.peek()) {
+        ('-', Some((_, '-'))) => {
+            chars.by_ref().find(|&(_, next)| next == '\n');
+        }
+        ('/', Some((_, '*'))) => {
+            chars.next();
+            let mut prev = ' ';
+            for (_, next) in chars.by_ref() {
+                if prev == '*' && next == '/' {
+                    break;
+                }
+                prev = next;
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// The words of `sql` outside quotes, comments and parentheses, with their
+/// byte offsets.
+fn top_level_words(sql: &str) -> Vec<(usize, &str)> {
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = sql.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => skip_quoted(&mut chars, c),
+            _ if skip_comment(&mut chars, c) => {}
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut end = i + 1;
+                while let Some(&(j, next)) = chars.peek() {
+                    if !(next.is_ascii_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    end = j + 1;
+                    chars.next();
+                }
+                if depth == 0 {
+                    words.push((i, &sql[i..end]));
+                }
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Append `settings` to the last statement of `sql` as a `SETTINGS` clause.
+///
+/// The clause goes on its own line, so a trailing `--` comment cannot
+/// swallow it, and before a `FORMAT` clause. If the statement already has a
+/// query-level `SETTINGS` clause, the settings are merged into it. The
+/// `SETTINGS` of a `CREATE` or `ATTACH` statement belong to its table
+/// engine, so those always get a clause of their own.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidData`] if a setting name is not a plain name.
+pub(crate) fn append_settings(sql: &str, settings: &[(String, Value)]) -> Result<String> {
+    let Some(last) = split_statements(sql).pop() else {
+        return Ok(sql.to_string());
+    };
+    if settings.is_empty() {
+        return Ok(sql.to_string());
+    }
+    let offset = last.as_ptr() as usize - sql.as_ptr() as usize;
+    let mut list = String::new();
+    write_setting_list(&mut list, settings)?;
+
+    let words = top_level_words(last);
+    let own_clause = !matches!(leading_keyword(last).as_str(), "CREATE" | "ATTACH");
+    let settings_at = words
+        .iter()
+        .rposition(|(_, w)| w.eq_ignore_ascii_case("SETTINGS"))
+        .filter(|_| own_clause);
+    let format_at = words[settings_at.unwrap_or(0)..]
+        .iter()
+        .find(|&&(i, w)| {
+            // `format(...)` is a function, not the clause.
+            w.eq_ignore_ascii_case("FORMAT") && !last[i + w.len()..].trim_start().starts_with('(')
+        })
+        .map(|&(i, _)| i);
+
+    let clause = match settings_at {
+        Some(_) => format!("\n, {list}"),
+        None => format!("\nSETTINGS {list}"),
+    };
+    let mut out = sql[..offset].to_string();
+    match format_at {
+        Some(i) => {
+            out.push_str(&last[..i]);
+            out.push_str(clause.trim_start());
+            out.push('\n');
+            out.push_str(&last[i..]);
+        }
+        None => {
+            out.push_str(last);
+            out.push_str(&clause);
+        }
+    }
+    Ok(out)
+}
+
+fn join_columns(names: &[String]) -> String {
+    names
+        .iter()
+        .map(|n| quote_column(n))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub(crate) fn write_settings(out: &mut String, settings: &[(String, Value)]) -> Result<()> {
+    if settings.is_empty() {
+        return Ok(());
+    }
+    out.push_str(" SETTINGS ");
+    write_setting_list(out, settings)
+}
+
+fn write_setting_list(out: &mut String, settings: &[(String, Value)]) -> Result<()> {
+    for (i, (name, value)) in settings.iter().enumerate() {
+        check_setting_name(name)?;
+        if i > 0 {
+            out.push_str(", ");
+        }
+        let _ = write!(out, "{name} = {}", value.to_sql());
+    }
+    Ok(())
+}
+
+/// Check that `name` is a plain setting name, which is written into SQL and
+/// command-line arguments unquoted.
+pub(crate) fn check_setting_name(name: &str) -> Result<()> {
+    if name.is_empty() || !name.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_') {
+        return Err(Error::InvalidData(format!("Invalid setting name '{name}'")));
+    }
+    Ok(())
+}
+
+/// Sort direction for [`Select::order_by`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+impl Order {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Asc => "ASC",
+            Self::Desc => "DESC",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum FromClause {
+    Table(String),
+    TableFunction(String),
+}
+
+#[derive(Debug, Clone)]
+enum Projection {
+    Column(String),
+    Expr(String),
+}
+
+/// Builder for `SELECT` statements.
+///
+/// Column names passed to [`columns`](Self::columns), [`group_by`](Self::group_by)
+/// and [`order_by`](Self::order_by) are quoted as identifiers. Use
+/// [`expr`](Self::expr) for computed projections and [`filter`](Self::filter)
+/// with `?` placeholders for conditions.
+#[derive(Debug, Clone)]
+pub struct Select {
+    from: FromClause,
+    projections: Vec<Projection>,
+    filters: Vec<(String, Vec<Value>)>,
+    group_by: Vec<String>,
+    order_by: Vec<(String, Order)>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+    settings: Vec<(String, Value)>,
+}
+
+impl Select {
+    /// Start a `SELECT` from the given table.
+    pub fn from(table: impl Into<String>) -> Self {
+        Self::with_from(FromClause::Table(table.into()))
+    }
+
+    /// Start a `SELECT` from a table function such as `file()` or `s3()`.
+    pub fn from_table_function(source: &TableFunction) -> Result<Self> {
+        Ok(Self::with_from(FromClause::TableFunction(source.to_sql()?)))
+    }
+
+    fn with_from(from: FromClause) -> Self {
+        Self {
+            from,
+            projections: Vec::new(),
+            filters: Vec::new(),
+            group_by: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            settings: Vec::new(),
+        }
+    }
+
+    /// Add columns to the projection. Selects `*` when no columns are given.
+    pub fn columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.projections
+            .extend(columns.into_iter().map(|c| Projection::Column(c.into())));
+        self
+    }
+
+    /// Add the columns of a [`Row`] type to the projection, in field order.
+    pub fn row_columns<T: Row>(self) -> Self {
+        self.columns(T::COLUMNS.iter().copied())
+    }
+
+    /// Add a raw SQL expression to the projection, e.g. `count() AS n`.
+    ///
+    /// The expression is used verbatim and must not contain untrusted input.
+    pub fn expr(mut self, expr: impl Into<String>) -> Self {
+        self.projections.push(Projection::Expr(expr.into()));
+        self
+    }
+
+    /// Add a `WHERE` condition. Multiple filters are combined with `AND`.
+    ///
+    /// Each `?` in `condition` is replaced by the next value from `params`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chdb_rust::query_builder::{Select, Value};
+    ///
+    /// let sql = Select::from("users")
+    ///     .filter("name = ? AND age > ?", [Value::from("O'Brien"), 30.into()])
+    ///     .to_sql()?;
+    /// assert_eq!(sql, "SELECT * FROM `users` WHERE (name = 'O\\'Brien' AND age > 30)");
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn filter<I, V>(mut self, condition: impl Into<String>, params: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.filters.push((
+            condition.into(),
+            params.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Add columns to the `GROUP BY` clause.
+    pub fn group_by<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.group_by.extend(columns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Add a column to the `ORDER BY` clause.
+    pub fn order_by(mut self, column: impl Into<String>, order: Order) -> Self {
+        self.order_by.push((column.into(), order));
+        self
+    }
+
+    /// Set the `LIMIT` clause.
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set the `OFFSET` clause.
+    pub fn offset(mut self, offset: u64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Add query-level settings, rendered as a trailing `SETTINGS` clause.
+    pub fn settings<I, K, V>(mut self, settings: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.settings
+            .extend(settings.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Render the statement as SQL.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] if the number of bound parameters in a
+    /// filter does not match its placeholders, or if a setting name contains
+    /// characters other than ASCII letters, digits and `_`.
+    pub fn to_sql(&self) -> Result<String> {
+        let mut sql = String::from("SELECT ");
+        if self.projections.is_empty() {
+            sql.push('*');
+        }
+        for (i, p) in self.projections.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            match p {
+                Projection::Column(c) => sql.push_str(&quote_column(c)),
+                Projection::Expr(e) => sql.push_str(e),
+            }
+        }
+        match &self.from {
+            FromClause::Table(table) => {
+                let _ = write!(sql, " FROM {}", quote_identifier(table));
+            }
+            FromClause::TableFunction(function) => {
+                let _ = write!(sql, " FROM {function}");
+            }
+        }
+
+        for (i, (condition, params)) in self.filters.iter().enumerate() {
+            sql.push_str(if i == 0 { " WHERE " } else { " AND " });
+            let _ = write!(sql, "({})", bind_params(condition, params)?);
+        }
+        if !self.group_by.is_empty() {
+            let _ = write!(sql, " GROUP BY {}", join_columns(&self.group_by));
+        }
+        for (i, (column, order)) in self.order_by.iter().enumerate() {
+            sql.push_str(if i == 0 { " ORDER BY " } else { ", " });
+            let _ = write!(sql, "{} {}", quote_column(column), order.as_str());
+        }
+        if let Some(limit) = self.limit {
+            let _ = write!(sql, " LIMIT {limit}");
+        }
+        if let Some(offset) = self.offset {
+            let _ = write!(sql, " OFFSET {offset}");
+        }
+        write_settings(&mut sql, &self.settings)?;
+        Ok(sql)
+    }
+
+    /// Execute the statement on a session.
+    ///
+    /// `query_args` are handled exactly as in [`Session::execute`].
+    pub fn execute(&self, session: &Session, query_args: Option<&[Arg]>) -> Result<QueryResult> {
+        session.execute(&self.to_sql()?, query_args)
+    }
+}
+
+/// Builder for `INSERT INTO ... VALUES` statements.
+#[derive(Debug, Clone)]
+pub struct Insert {
+    table: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+    settings: Vec<(String, Value)>,
+}
+
+impl Insert {
+    /// Start an `INSERT` into the given table.
+    pub fn into(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+            settings: Vec::new(),
+        }
+    }
+
+    /// Set the target columns. When omitted, values must cover every column.
+    pub fn columns<I, S>(mut self, columns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.columns.extend(columns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Append a row of values.
+    pub fn row<I, V>(mut self, values: I) -> Self
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<Value>,
+    {
+        self.rows.push(values.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Add insert settings, rendered as a `SETTINGS` clause.
+    pub fn settings<I, K, V>(mut self, settings: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.settings
+            .extend(settings.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Render the statement as SQL.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] if there are no rows, if a row's width
+    /// does not match the column list, or if a setting name contains
+    /// characters other than ASCII letters, digits and `_`.
+    pub fn to_sql(&self) -> Result<String> {
+        if self.rows.is_empty() {
+            return Err(Error::InvalidData(format!(
+                "INSERT into '{}' has no rows",
+                self.table
+            )));
+        }
+
+        let mut sql = format!("INSERT INTO {}", quote_identifier(&self.table));
+        if !self.columns.is_empty() {
+            let _ = write!(sql, " ({})", join_columns(&self.columns));
+        }
+        write_settings(&mut sql, &self.settings)?;
+        sql.push_str(" VALUES ");
+
+        for (i, row) in self.rows.iter().enumerate() {
+            if !self.columns.is_empty() && row.len() != self.columns.len() {
+                return Err(Error::InvalidData(format!(
+                    "Row {i} has {} values, expected {}",
+                    row.len(),
+                    self.columns.len()
+                )));
+            }
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            sql.push('(');
+            for (j, value) in row.iter().enumerate() {
+                if j > 0 {
+                    sql.push_str(", ");
+                }
+                value.write_sql(&mut sql);
+            }
+            sql.push(')');
+        }
+        Ok(sql)
+    }
+
+    /// Execute the statement on a session.
+    pub fn execute(&self, session: &Session, query_args: Option<&[Arg]>) -> Result<QueryResult> {
+        session.execute(&self.to_sql()?, query_args)
+    }
+}
+
+/// Builder for `CREATE TABLE` statements.
+///
+/// Column types, the engine and the `ORDER BY`, `PRIMARY KEY`,
+/// `PARTITION BY` and `TTL` expressions are written verbatim; only table and
+/// column names are quoted.
+#[derive(Debug, Clone)]
+pub struct CreateTable {
+    table: String,
+    if_not_exists: bool,
+    columns: Vec<(String, String)>,
+    engine: String,
+    order_by: Vec<String>,
+    partition_by: Option<String>,
+    primary_key: Vec<String>,
+    ttl: Option<String>,
+    settings: Vec<(String, Value)>,
+}
+
+impl CreateTable {
+    /// Start a `CREATE TABLE` statement. The engine defaults to `MergeTree()`.
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            if_not_exists: false,
+            columns: Vec::new(),
+            engine: "MergeTree()".to_string(),
+            order_by: Vec::new(),
+            partition_by: None,
+            primary_key: Vec::new(),
+            ttl: None,
+            settings: Vec::new(),
+        }
+    }
+
+    /// Add `IF NOT EXISTS`.
+    pub fn if_not_exists(mut self) -> Self {
+        self.if_not_exists = true;
+        self
+    }
+
+    /// Add a column with the given ClickHouse type, e.g. `Nullable(String)`.
+    pub fn column(mut self, name: impl Into<String>, ty: impl Into<String>) -> Self {
+        self.columns.push((name.into(), ty.into()));
+        self
+    }
+
+    /// Set the table engine, e.g. `ReplacingMergeTree(version)`.
+    pub fn engine(mut self, engine: impl Into<String>) -> Self {
+        self.engine = engine.into();
+        self
+    }
+
+    /// Set the `ORDER BY` key expressions, e.g. `["kind", "toStartOfHour(ts)"]`.
+    ///
+    /// The expressions are written verbatim; quote column names that need it
+    /// with [`quote_column`]. MergeTree engines require a sorting key; when
+    /// none is given `ORDER BY tuple()` is emitted.
+    pub fn order_by<I, S>(mut self, exprs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.order_by.extend(exprs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the `PARTITION BY` expression.
+    pub fn partition_by(mut self, expr: impl Into<String>) -> Self {
+        self.partition_by = Some(expr.into());
+        self
+    }
+
+    /// Set the `PRIMARY KEY` expressions when they differ from the sorting
+    /// key. Like [`order_by`](Self::order_by), they are written verbatim.
+    pub fn primary_key<I, S>(mut self, exprs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.primary_key.extend(exprs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the table `TTL` expression, e.g. `ts + INTERVAL 30 DAY`.
+    pub fn ttl(mut self, expr: impl Into<String>) -> Self {
+        self.ttl = Some(expr.into());
+        self
+    }
+
+    /// Add table settings, rendered as a `SETTINGS` clause.
+    pub fn settings<I, K, V>(mut self, settings: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        self.settings
+            .extend(settings.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Render the statement as SQL.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidData`] if no columns were added, or if a
+    /// setting name contains characters other than ASCII letters, digits and
+    /// `_`.
+    pub fn to_sql(&self) -> Result<String> {
+        if self.columns.is_empty() {
+            return Err(Error::InvalidData(format!(
+                "CREATE TABLE '{}' has no columns",
+                self.table
+            )));
+        }
+
+        let mut sql = String::from("CREATE TABLE ");
+        if self.if_not_exists {
+            sql.push_str("IF NOT EXISTS ");
+        }
+        sql.push_str(&quote_identifier(&self.table));
+        sql.push_str(" (");
+        for (i, (name, ty)) in self.columns.iter().enumerate() {
+            if i > 0 {
+                sql.push_str(", ");
+            }
+            let _ = write!(sql, "{} {ty}", quote_column(name));
+        }
+        let _ = write!(sql, ") ENGINE = {}", self.engine);
+
+        if let Some(partition_by) = &self.partition_by {
+            let _ = write!(sql, " PARTITION BY {partition_by}");
+        }
+        if self.order_by.is_empty() {
+            if self.engine.contains("MergeTree") {
+                sql.push_str(" ORDER BY tuple()");
+            }
+        } else {
+            let _ = write!(sql, " ORDER BY ({})", self.order_by.join(", "));
+        }
+        if !self.primary_key.is_empty() {
+            let _ = write!(sql, " PRIMARY KEY ({})", self.primary_key.join(", "));
+        }
+        if let Some(ttl) = &self.ttl {
+            let _ = write!(sql, " TTL {ttl}");
+        }
+        write_settings(&mut sql, &self.settings)?;
+        Ok(sql)
+    }
+
+    /// Execute the statement on a session.
+    pub fn execute(&self, session: &Session, query_args: Option<&[Arg]>) -> Result<QueryResult> {
+        session.execute(&self.to_sql()?, query_args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(split_statements("SELECT 1"), ["SELECT 1"]);
+        assert_eq!(split_statements(" SELECT 1; -- done\n"), ["SELECT 1"]);
+        assert_eq!(
+            split_statements("SELECT ';DROP TABLE t' /* ; x */"),
+            ["SELECT ';DROP TABLE t' /* ; x */"]
+        );
+        assert_eq!(split_statements("SELECT 'it\\'s;x'"), ["SELECT 'it\\'s;x'"]);
+        assert_eq!(
+            split_statements(
+                "CREATE TABLE t (id UInt64) ENGINE = Memory;\n-- seed\nINSERT INTO t VALUES (1);"
+            ),
+            [
+                "CREATE TABLE t (id UInt64) ENGINE = Memory",
+                "-- seed\nINSERT INTO t VALUES (1)"
+            ]
+        );
+        assert_eq!(split_statements("SELECT 1;/**/`t`").len(), 2);
+        assert!(split_statements(" ; -- nothing").is_empty());
+    }
+
+    #[test]
+    fn test_append_settings() {
+        let settings = [("log_comment".to_string(), Value::from("id"))];
+        assert_eq!(
+            append_settings("SELECT 1 -- done\n", &settings).unwrap(),
+            "SELECT 1 -- done\nSETTINGS log_comment = 'id'"
+        );
+        assert_eq!(
+            append_settings("SELECT 1; -- done", &settings).unwrap(),
+            "SELECT 1\nSETTINGS log_comment = 'id'"
+        );
+        assert_eq!(
+            append_settings("SELECT 1 SETTINGS max_threads = 1", &settings).unwrap(),
+            "SELECT 1 SETTINGS max_threads = 1\n, log_comment = 'id'"
+        );
+        assert_eq!(
+            append_settings("SELECT 1 settings max_threads = 1 FORMAT CSV", &settings).unwrap(),
+            "SELECT 1 settings max_threads = 1 , log_comment = 'id'\nFORMAT CSV"
+        );
+        assert_eq!(
+            append_settings("SELECT format('{}', 'x') FORMAT CSV", &settings).unwrap(),
+            "SELECT format('{}', 'x') SETTINGS log_comment = 'id'\nFORMAT CSV"
+        );
+        assert_eq!(
+            append_settings("SELECT (SELECT 1 SETTINGS max_threads = 1)", &settings).unwrap(),
+            "SELECT (SELECT 1 SETTINGS max_threads = 1)\nSETTINGS log_comment = 'id'"
+        );
+        assert_eq!(
+            append_settings(
+                "CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id SETTINGS index_granularity = 1",
+                &settings
+            )
+            .unwrap(),
+            "CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id SETTINGS index_granularity = 1\n\
+             SETTINGS log_comment = 'id'"
+        );
+        assert_eq!(append_settings("SELECT 1", &[]).unwrap(), "SELECT 1");
+    }
+
+    #[test]
+    fn test_quote_identifier_escapes() {
+        assert_eq!(quote_identifier("a`b"), "`a\\`b`");
+        assert_eq!(quote_identifier("db.t"), "`db`.`t`");
+        assert_eq!(quote_column("n.x"), "`n.x`");
+        assert_eq!(quote_column("a`b"), "`a\\`b`");
+    }
+
+    #[test]
+    fn test_quote_bytes() {
+        assert_eq!(quote_bytes(b"a'\0\xff"), "'a\\'\\x00\\xff'");
+    }
+
+    #[test]
+    fn test_value_literals() {
+        assert_eq!(Value::from("a'b\\c").to_sql(), "'a\\'b\\\\c'");
+        assert_eq!(Value::from(None::<i32>).to_sql(), "NULL");
+        assert_eq!(Value::from(vec![1u8, 2]).to_sql(), "[1, 2]");
+        assert_eq!(Value::from(1.0f64).to_sql(), "1.0");
+    }
+
+    #[test]
+    fn test_select_full() {
+        let sql = Select::from("events")
+            .columns(["kind"])
+            .expr("count() AS n")
+            .filter("ts > ?", [100])
+            .filter("kind != '?'", Vec::<Value>::new())
+            .group_by(["kind"])
+            .order_by("kind", Order::Desc)
+            .limit(5)
+            .settings([("max_threads", 2)])
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT `kind`, count() AS n FROM `events` WHERE (ts > 100) AND (kind != '?') \
+             GROUP BY `kind` ORDER BY `kind` DESC LIMIT 5 SETTINGS max_threads = 2"
+        );
+    }
+
+    #[test]
+    fn test_setting_name_injection() {
+        let bad = "max_threads = 1, readonly";
+        assert!(Select::from("t").settings([(bad, 0)]).to_sql().is_err());
+        assert!(Insert::into("t")
+            .row([Value::from(1)])
+            .settings([(bad, 0)])
+            .to_sql()
+            .is_err());
+        assert!(CreateTable::new("t")
+            .column("id", "UInt64")
+            .settings([("index_granularity", 1), ("", 0)])
+            .to_sql()
+            .is_err());
+        assert!(check_setting_name("max_threads").is_ok());
+    }
+
+    #[test]
+    fn test_bind_params_skips_comments() {
+        assert_eq!(
+            bind_params("a = ? -- b = ?\nAND c = ? /* ? */", &[1.into(), 2.into()]).unwrap(),
+            "a = 1 -- b = ?\nAND c = 2 /* ? */"
+        );
+        assert_eq!(
+            bind_params("s = 'it\\'s ?' AND t = ?", &["x".into()]).unwrap(),
+            "s = 'it\\'s ?' AND t = 'x'"
+        );
+        assert!(bind_params("a = ? -- ?", &[1.into(), 2.into()]).is_err());
+    }
+
+    #[test]
+    fn test_select_param_mismatch() {
+        assert!(Select::from("t")
+            .filter("a = ?", Vec::<Value>::new())
+            .to_sql()
+            .is_err());
+        assert!(Select::from("t").filter("a = 1", [1]).to_sql().is_err());
+    }
+
+    #[test]
+    fn test_insert() {
+        let sql = Insert::into("t")
+            .columns(["id", "name"])
+            .row([Value::from(1), "x".into()])
+            .row([Value::from(2), Value::Null])
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "INSERT INTO `t` (`id`, `name`) VALUES (1, 'x'), (2, NULL)"
+        );
+        assert!(Insert::into("t")
+            .columns(["a"])
+            .row([1, 2])
+            .to_sql()
+            .is_err());
+    }
+
+    #[test]
+    fn test_create_table() {
+        let sql = CreateTable::new("logs")
+            .if_not_exists()
+            .column("ts", "DateTime")
+            .column("msg", "String")
+            .column("attrs.key", "Array(String)")
+            .partition_by("toYYYYMM(ts)")
+            .order_by(["toStartOfHour(ts)", "msg"])
+            .primary_key(["toStartOfHour(ts)"])
+            .ttl("ts + INTERVAL 30 DAY")
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "CREATE TABLE IF NOT EXISTS `logs` (`ts` DateTime, `msg` String, \
+             `attrs.key` Array(String)) ENGINE = MergeTree() PARTITION BY toYYYYMM(ts) \
+             ORDER BY (toStartOfHour(ts), msg) PRIMARY KEY (toStartOfHour(ts)) \
+             TTL ts + INTERVAL 30 DAY"
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Query identifiers and `system.query_log` records.
+//!
+//! [`Connection::query_with_id`] tags a query with an id, either chosen by
+//! the caller or generated. chDB assigns its own internal query ids, so the
+//! tag is carried in the `log_comment` setting, which ClickHouse records in
+//! `system.query_log`. [`Session::query_log`] looks the query up by that id.
+//!
+//! [`Connection::query_with_id`]: crate::connection::Connection::query_with_id
+//! [`Session::query_log`]: crate::session::Session::query_log
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::format::OutputFormat;
+//! use chdb_rust::session::SessionBuilder;
+//!
+//! let session = SessionBuilder::new().with_data_path("/tmp/mydb").build()?;
+//! let result = session.connection().query_with_id(
+//!     "SELECT count() FROM numbers(1000000)",
+//!     OutputFormat::CSV,
+//!     None,
+//! )?;
+//! let query_id = result.query_id().unwrap();
+//!
+//! if let Some(entry) = session.query_log(query_id)? {
+//!     println!("{query_id} took {:?} and used {} bytes", entry.duration, entry.memory_usage);
+//! }
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::error::{ErrorCode, Result};
+use crate::query_builder::quote_literal;
+use crate::row::RowBinary;
+
+/// A finished query, as recorded in `system.query_log`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryLogEntry {
+    /// The id the query was tagged with.
+    pub query_id: String,
+    /// The id chDB assigned internally, as used by other system tables.
+    pub server_query_id: String,
+    /// The query text.
+    pub query: String,
+    /// Wall-clock execution time, with millisecond precision.
+    pub duration: Duration,
+    /// Peak memory usage in bytes.
+    pub memory_usage: u64,
+    /// Rows read from tables and table functions.
+    pub read_rows: u64,
+    /// Bytes read from tables and table functions.
+    pub read_bytes: u64,
+    /// Rows written, for `INSERT` queries.
+    pub written_rows: u64,
+    /// Rows in the result.
+    pub result_rows: u64,
+    /// Bytes in the result.
+    pub result_bytes: u64,
+    /// ClickHouse profile event counters, e.g. `SelectedRows` or `OSCPUVirtualTimeMicroseconds`.
+    pub profile_events: BTreeMap<String, u64>,
+    /// The error code, if the query failed.
+    pub exception_code: Option<ErrorCode>,
+    /// The error message, if the query failed.
+    pub exception: Option<String>,
+}
+
+impl QueryLogEntry {
+    /// Whether the query failed.
+    pub fn is_error(&self) -> bool {
+        self.exception_code.is_some()
+    }
+
+    /// The `SELECT` reading the latest finished entry tagged with `query_id`,
+    /// in the column order expected by [`decode`](Self::decode).
+    pub(crate) fn select_sql(query_id: &str) -> String {
+        format!(
+            "SELECT log_comment, query_id, query, toUInt64(query_duration_ms), \
+             toUInt64(memory_usage), read_rows, read_bytes, written_rows, result_rows, \
+             result_bytes, mapKeys(ProfileEvents), mapValues(ProfileEvents), \
+             toInt32(exception_code), exception \
+             FROM system.query_log \
+             WHERE log_comment = {} AND type != 'QueryStart' \
+             ORDER BY event_time_microseconds DESC LIMIT 1",
+            quote_literal(query_id)
+        )
+    }
+
+    /// Decode a `RowBinary` row of [`select_sql`](Self::select_sql).
+    pub(crate) fn decode(buf: &mut &[u8]) -> Result<Self> {
+        let query_id = String::decode(buf)?;
+        let server_query_id = String::decode(buf)?;
+        let query = String::decode(buf)?;
+        let duration = Duration::from_millis(u64::decode(buf)?);
+        let memory_usage = u64::decode(buf)?;
+        let read_rows = u64::decode(buf)?;
+        let read_bytes = u64::decode(buf)?;
+        let written_rows = u64::decode(buf)?;
+        let result_rows = u64::decode(buf)?;
+        let result_bytes = u64::decode(buf)?;
+        let names = Vec::<String>::decode(buf)?;
+        let values = Vec::<u64>::decode(buf)?;
+        let exception_code = i32::decode(buf)?;
+        let exception = String::decode(buf)?;
+        Ok(Self {
+            query_id,
+            server_query_id,
+            query,
+            duration,
+            memory_usage,
+            read_rows,
+            read_bytes,
+            written_rows,
+            result_rows,
+            result_bytes,
+            profile_events: names.into_iter().zip(values).collect(),
+            exception_code: (exception_code != 0).then(|| ErrorCode(exception_code.unsigned_abs())),
+            exception: (!exception.is_empty()).then_some(exception),
+        })
+    }
+}
+
+/// Generate a random query id formatted as a UUID (version 4).
+pub(crate) fn generate_query_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let random = |salt: u64| {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        hasher.write_u64(salt);
+        if let Ok(now) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+            hasher.write_u128(now.as_nanos());
+        }
+        hasher.finish()
+    };
+    let bits = (u128::from(random(1)) << 64 | u128::from(random(2)))
+        & !(0xf000 << 64 | 0xc000 << 48)
+        | (0x4000 << 64 | 0x8000 << 48);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        bits >> 96,
+        (bits >> 80) & 0xffff,
+        (bits >> 64) & 0xffff,
+        (bits >> 48) & 0xffff,
+        bits & 0xffff_ffff_ffff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_query_id() {
+        let id = generate_query_id();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+        assert!(matches!(&id[19..20], "8" | "9" | "a" | "b"));
+        assert_ne!(id, generate_query_id());
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut buf = Vec::new();
+        "tag".to_string().encode(&mut buf);
+        "uuid".to_string().encode(&mut buf);
+        "SELECT 1".to_string().encode(&mut buf);
+        for value in [1500u64, 1024, 1, 1, 0, 1, 2] {
+            value.encode(&mut buf);
+        }
+        vec!["SelectedRows".to_string()].encode(&mut buf);
+        vec![1u64].encode(&mut buf);
+        60i32.encode(&mut buf);
+        "Unknown table".to_string().encode(&mut buf);
+
+        let entry = QueryLogEntry::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(entry.query_id, "tag");
+        assert_eq!(entry.duration, Duration::from_millis(1500));
+        assert_eq!(entry.memory_usage, 1024);
+        assert_eq!(entry.result_bytes, 2);
+        assert_eq!(entry.profile_events["SelectedRows"], 1);
+        assert_eq!(entry.exception_code, Some(ErrorCode(60)));
+        assert!(entry.is_error());
+    }
+}
@@ -44,6 +44,7 @@ use crate::error::Result;
 #[derive(Debug)]
 pub struct QueryResult {
     inner: *mut bindings::chdb_result,
+    query_id: Option<String>,
 }
 
 // Safety: QueryResult is safe to send between threads
@@ -52,7 +53,21 @@ unsafe impl Send for QueryResult {}
 
 impl QueryResult {
     pub(crate) fn new(inner: *mut bindings::chdb_result) -> Self {
-        Self { inner }
+        Self {
+            inner,
+            query_id: None,
+        }
+    }
+
+    pub(crate) fn with_query_id(mut self, query_id: String) -> Self {
+        self.query_id = Some(query_id);
+        self
+    }
+
+    /// The id the query was tagged with, if it was run with
+    /// [`Connection::query_with_id`](crate::connection::Connection::query_with_id).
+    pub fn query_id(&self) -> Option<&str> {
+        self.query_id.as_deref()
     }
 
     /// Get the result data as a UTF-8 string.
@@ -235,19 +250,24 @@ impl QueryResult {
     }
 
     pub(crate) fn check_error_ref(&self) -> Result<()> {
-        let err_ptr = unsafe { bindings::chdb_result_error(self.inner) };
+        check_raw_error(self.inner)
+    }
+}
 
-        if err_ptr.is_null() {
-            return Ok(());
-        }
+/// Check a raw result handle for an error message without taking ownership.
+pub(crate) fn check_raw_error(result: *mut bindings::chdb_result) -> Result<()> {
+    let err_ptr = unsafe { bindings::chdb_result_error(result) };
 
-        let err_msg = unsafe { CStr::from_ptr(err_ptr).to_string_lossy().to_string() };
-        if err_msg.is_empty() {
-            return Ok(());
-        }
+    if err_ptr.is_null() {
+        return Ok(());
+    }
 
-        Err(Error::QueryError(err_msg))
+    let err_msg = unsafe { CStr::from_ptr(err_ptr).to_string_lossy().to_string() };
+    if err_msg.is_empty() {
+        return Ok(());
     }
+
+    Err(Error::QueryError(err_msg))
 }
 
 impl Drop for QueryResult {
@@ -0,0 +1,299 @@
+//! Typed rows encoded with ClickHouse's `RowBinary` format.
+//!
+//! The [`Row`] trait maps a Rust struct to a table: its column list, column
+//! definitions for `CREATE TABLE`, and `RowBinary` encoding and decoding.
+//! Implementations are normally generated with `#[derive(ChdbRow)]` (enabled
+//! by the `derive` feature) and used through
+//! [`Session::query_rows`](crate::session::Session::query_rows) and
+//! [`Session::insert_rows`](crate::session::Session::insert_rows).
+//!
+//! Individual field types implement [`RowBinary`], which covers integers,
+//! floats, `bool`, `String`, `Option<T>` (as `Nullable`) and `Vec<T>` (as `Array`).
+//!
+//! # Examples
+//!
+//! ```ignore
+//! use chdb_rust::ChdbRow;
+//!
+//! #[derive(ChdbRow)]
+//! struct Event {
+//!     #[chdb(order_by)]
+//!     id: u64,
+//!     #[chdb(type = "LowCardinality(String)")]
+//!     kind: String,
+//!     #[chdb(codec = "ZSTD(3)")]
+//!     payload: String,
+//! }
+//!
+//! session.execute(&Event::create_table("events").to_sql()?, None)?;
+//! session.insert_rows("events", &[Event { id: 1, kind: "click".into(), payload: "{}".into() }])?;
+//! let events: Vec<Event> = session.query_rows("SELECT * FROM events")?;
+//! ```
+
+use crate::error::{Error, Result};
+use crate::query_builder::{quote_column, CreateTable};
+
+/// A value that can be encoded to and decoded from `RowBinary`.
+pub trait RowBinary: Sized {
+    /// The ClickHouse type this Rust type maps to by default.
+    fn clickhouse_type() -> String;
+
+    /// Append the `RowBinary` encoding of `self` to `buf`.
+    fn encode(&self, buf: &mut Vec<u8>);
+
+    /// Decode a value from the front of `buf`, advancing it.
+    fn decode(buf: &mut &[u8]) -> Result<Self>;
+}
+
+/// A column of a [`Row`] type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnDef {
+    /// Column name.
+    pub name: &'static str,
+    /// ClickHouse type, e.g. `LowCardinality(String)`.
+    pub ty: String,
+    /// Optional compression codec, e.g. `ZSTD(3)`.
+    pub codec: Option<&'static str>,
+    /// Whether the column is part of the `ORDER BY` key.
+    pub order_by: bool,
+}
+
+/// A Rust type that maps to a table row.
+pub trait Row: Sized {
+    /// Column names, in field order.
+    const COLUMNS: &'static [&'static str];
+
+    /// Column definitions, in field order.
+    fn column_defs() -> Vec<ColumnDef>;
+
+    /// Append the `RowBinary` encoding of this row to `buf`.
+    fn encode_row(&self, buf: &mut Vec<u8>);
+
+    /// Decode one row from the front of `buf`, advancing it.
+    fn decode_row(buf: &mut &[u8]) -> Result<Self>;
+
+    /// The row structure as accepted by the `format()` table function,
+    /// e.g. `id UInt64, name String`.
+    fn structure() -> String {
+        Self::column_defs()
+            .iter()
+            .map(|c| format!("{} {}", quote_column(c.name), c.ty))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A `CREATE TABLE` builder for this row type.
+    ///
+    /// Columns marked `order_by` form the sorting key; further clauses such as
+    /// the engine or `PARTITION BY` can be added to the returned builder.
+    fn create_table(table: &str) -> CreateTable {
+        let defs = Self::column_defs();
+        let mut builder = CreateTable::new(table);
+        for def in &defs {
+            let ty = match def.codec {
+                Some(codec) => format!("{} CODEC({codec})", def.ty),
+                None => def.ty.clone(),
+            };
+            builder = builder.column(def.name, ty);
+        }
+        builder.order_by(
+            defs.iter()
+                .filter(|d| d.order_by)
+                .map(|d| quote_column(d.name)),
+        )
+    }
+}
+
+/// Decode every row in a `RowBinary` buffer.
+pub fn decode_rows<T: Row>(mut buf: &[u8]) -> Result<Vec<T>> {
+    let mut rows = Vec::new();
+    while !buf.is_empty() {
+        rows.push(T::decode_row(&mut buf)?);
+    }
+    Ok(rows)
+}
+
+/// Encode rows into a single `RowBinary` buffer.
+pub fn encode_rows<T: Row>(rows: &[T]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    for row in rows {
+        row.encode_row(&mut buf);
+    }
+    buf
+}
+
+fn take<'a>(buf: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if buf.len() < n {
+        return Err(Error::InvalidData(
+            "Unexpected end of RowBinary data".to_string(),
+        ));
+    }
+    let (head, tail) = buf.split_at(n);
+    *buf = tail;
+    Ok(head)
+}
+
+fn write_varint(mut value: u64, buf: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buf.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn read_varint(buf: &mut &[u8]) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = take(buf, 1)?[0];
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(Error::InvalidData("Invalid RowBinary varint".to_string()))
+}
+
+macro_rules! impl_row_binary_number {
+    ($($t:ty => $name:literal),*) => {
+        $(
+            impl RowBinary for $t {
+                fn clickhouse_type() -> String {
+                    $name.to_string()
+                }
+
+                fn encode(&self, buf: &mut Vec<u8>) {
+                    buf.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(buf: &mut &[u8]) -> Result<Self> {
+                    let bytes = take(buf, std::mem::size_of::<$t>())?;
+                    Ok(<$t>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_row_binary_number!(
+    u8 => "UInt8", u16 => "UInt16", u32 => "UInt32", u64 => "UInt64", u128 => "UInt128",
+    i8 => "Int8", i16 => "Int16", i32 => "Int32", i64 => "Int64", i128 => "Int128",
+    f32 => "Float32", f64 => "Float64"
+);
+
+impl RowBinary for bool {
+    fn clickhouse_type() -> String {
+        "Bool".to_string()
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        buf.push(u8::from(*self));
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self> {
+        Ok(take(buf, 1)?[0] != 0)
+    }
+}
+
+impl RowBinary for String {
+    fn clickhouse_type() -> String {
+        "String".to_string()
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        write_varint(self.len() as u64, buf);
+        buf.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self> {
+        let len = read_varint(buf)? as usize;
+        String::from_utf8(take(buf, len)?.to_vec()).map_err(Error::NonUtf8Sequence)
+    }
+}
+
+impl<T: RowBinary> RowBinary for Option<T> {
+    fn clickhouse_type() -> String {
+        format!("Nullable({})", T::clickhouse_type())
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        match self {
+            None => buf.push(1),
+            Some(v) => {
+                buf.push(0);
+                v.encode(buf);
+            }
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self> {
+        if take(buf, 1)?[0] != 0 {
+            Ok(None)
+        } else {
+            T::decode(buf).map(Some)
+        }
+    }
+}
+
+impl<T: RowBinary> RowBinary for Vec<T> {
+    fn clickhouse_type() -> String {
+        format!("Array({})", T::clickhouse_type())
+    }
+
+    fn encode(&self, buf: &mut Vec<u8>) {
+        write_varint(self.len() as u64, buf);
+        for v in self {
+            v.encode(buf);
+        }
+    }
+
+    fn decode(buf: &mut &[u8]) -> Result<Self> {
+        let len = read_varint(buf)? as usize;
+        (0..len).map(|_| T::decode(buf)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: RowBinary + PartialEq + std::fmt::Debug>(value: T) {
+        let mut buf = Vec::new();
+        value.encode(&mut buf);
+        let mut slice = buf.as_slice();
+        assert_eq!(T::decode(&mut slice).unwrap(), value);
+        assert!(slice.is_empty());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        round_trip(42u64);
+        round_trip(-7i32);
+        round_trip(1.5f64);
+        round_trip(true);
+        round_trip("x".repeat(300));
+        round_trip(Some(3u8));
+        round_trip(None::<String>);
+        round_trip(vec![1u16, 2, 3]);
+    }
+
+    #[test]
+    fn test_string_encoding() {
+        let mut buf = Vec::new();
+        "abc".to_string().encode(&mut buf);
+        assert_eq!(buf, b"\x03abc");
+    }
+
+    #[test]
+    fn test_truncated_input() {
+        let mut buf: &[u8] = &[1, 2];
+        assert!(u32::decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_types() {
+        assert_eq!(
+            Vec::<Option<i64>>::clickhouse_type(),
+            "Array(Nullable(Int64))"
+        );
+    }
+}
@@ -4,13 +4,41 @@
 //! persistent database connections with automatic cleanup.
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use crate::arg::Arg;
+use crate::arg::{extract_format_options, Arg};
+use crate::backup::{BackupInfo, BackupOptions};
 use crate::connection::Connection;
+use crate::dir_lock::DirLock;
 use crate::error::Error;
-use crate::format::OutputFormat;
+use crate::explain::{Explain, ExplainKind};
+use crate::format::InputFormat;
+use crate::format::{FormatOptions, OutputFormat};
+use crate::instrument::Instrument;
+#[cfg(any(feature = "log", feature = "tracing"))]
+use crate::log_capture::{LogCapture, LOG_FILE_NAME};
+#[cfg(any(feature = "log", feature = "tracing"))]
+use crate::log_level::LogLevel;
+use crate::query_builder::{
+    quote_bytes, quote_column, quote_identifier, quote_literal, split_statements, Select,
+};
+use crate::query_log::QueryLogEntry;
 use crate::query_result::QueryResult;
+use crate::row::{decode_rows, Row};
+use crate::snapshot;
+use crate::table_function::{ColumnSchema, TableFunction};
+
+/// Maximum size of the `RowBinary` payload sent per `INSERT` statement.
+///
+/// Payloads are embedded as escaped string literals, which can be up to four
+/// times larger than the raw bytes, so this keeps each statement well below
+/// ClickHouse's default `max_query_size` of 256 KiB.
+const INSERT_CHUNK_SIZE: usize = 48 * 1024;
+
+/// ClickHouse's default `max_query_size`, in bytes.
+const DEFAULT_MAX_QUERY_SIZE: usize = 256 * 1024;
 
 /// Builder for creating [`Session`] instances.
 ///
@@ -33,8 +61,17 @@ use crate::query_result::QueryResult;
 pub struct SessionBuilder<'a> {
     data_path: PathBuf,
     default_format: OutputFormat,
+    default_format_options: Option<FormatOptions>,
     _marker: std::marker::PhantomData<&'a ()>,
-    auto_cleanup: bool,
+    /// `None` unless set explicitly: temporary sessions clean up by default.
+    auto_cleanup: Option<bool>,
+    temporary: bool,
+    read_only: bool,
+    lock_timeout: Option<Duration>,
+    snapshot: Option<String>,
+    backup_dir: Option<PathBuf>,
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    log_capture: Option<LogLevel>,
 }
 
 /// A session representing a persistent connection to a chDB database.
@@ -84,15 +121,24 @@ pub struct Session {
     data_path: String,
     default_format: OutputFormat,
     auto_cleanup: bool,
+    read_only: bool,
+    /// Declared after `conn` so that it is released once the connection is closed.
+    _lock: Option<DirLock>,
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    log_capture: Option<LogCapture>,
 }
 
 impl<'a> SessionBuilder<'a> {
     /// Create a new `SessionBuilder` with default settings.
     ///
     /// The default settings are:
-    /// - Data path: `./chdb` in the current working directory
+    /// - Data path: `./chdb` in the current working directory, or `chdb`
+    ///   relative to it if the current directory cannot be determined
     /// - Output format: `TabSeparated`
     /// - Auto cleanup: `false`
+    /// - Read only: `false`
+    /// - Data directory lock: enabled, failing immediately if held
+    /// - Backup directory: none
     ///
     /// # Examples
     ///
@@ -103,14 +149,23 @@ impl<'a> SessionBuilder<'a> {
     /// # Ok::<(), chdb_rust::error::Error>(())
     /// ```
     pub fn new() -> Self {
-        let mut data_path = std::env::current_dir().unwrap();
-        data_path.push("chdb");
+        let data_path = std::env::current_dir()
+            .map(|dir| dir.join("chdb"))
+            .unwrap_or_else(|_| PathBuf::from("chdb"));
 
         Self {
             data_path,
             default_format: OutputFormat::TabSeparated,
+            default_format_options: None,
             _marker: std::marker::PhantomData,
-            auto_cleanup: false,
+            auto_cleanup: None,
+            temporary: false,
+            read_only: false,
+            lock_timeout: Some(Duration::ZERO),
+            snapshot: None,
+            backup_dir: None,
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            log_capture: None,
         }
     }
 
@@ -119,6 +174,10 @@ impl<'a> SessionBuilder<'a> {
     /// This specifies the filesystem path where the database will be stored.
     /// The directory will be created if it doesn't exist.
     ///
+    /// This replaces a [temporary](Self::temporary) directory, along with its
+    /// automatic cleanup: the directory at `path` is only removed if
+    /// [auto cleanup](Self::with_auto_cleanup) is enabled explicitly.
+    ///
     /// # Arguments
     ///
     /// * `path` - The path where the database should be stored
@@ -134,17 +193,73 @@ impl<'a> SessionBuilder<'a> {
     /// ```
     pub fn with_data_path(mut self, path: impl Into<PathBuf>) -> Self {
         self.data_path = path.into();
+        self.temporary = false;
         self
     }
 
+    /// Store the session in a new, uniquely named directory under the
+    /// system's temporary directory.
+    ///
+    /// Unless [auto cleanup](Self::with_auto_cleanup) is disabled
+    /// explicitly, the directory is removed when the session is dropped,
+    /// including while unwinding from a panic, or when [`build`](Self::build)
+    /// fails. Use [`Session::data_path`] to find where it was created. A later
+    /// [`with_data_path`](Self::with_data_path) replaces the temporary
+    /// directory and its cleanup.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new().temporary().build()?;
+    /// let path = session.data_path().to_path_buf();
+    /// assert!(path.exists());
+    ///
+    /// drop(session);
+    /// assert!(!path.exists());
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn temporary(mut self) -> Self {
+        self.data_path = temporary_data_path();
+        self.temporary = true;
+        self
+    }
+
+    /// Start a [temporary](Self::temporary) session from a copy of the
+    /// snapshot `name`, saved with [`Session::snapshot`].
+    ///
+    /// See [`snapshot`](crate::snapshot) for how snapshots are stored.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new().from_snapshot("users").build()?;
+    /// let result = session.execute("SELECT count() FROM users", None)?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn from_snapshot(self, name: impl Into<String>) -> Self {
+        let mut builder = self.temporary();
+        builder.snapshot = Some(name.into());
+        builder
+    }
+
     /// Add a query argument to the session builder.
     ///
-    /// Currently, only `OutputFormat` arguments are supported and will be used
-    /// as the default output format for queries executed on this session.
+    /// Currently, only `OutputFormat` and `FormatOptions` arguments are
+    /// supported and will be used as the defaults for queries executed on this
+    /// session. Arguments passed to [`Session::execute`] take precedence.
+    ///
+    /// `FormatOptions` are passed to chDB as settings when the connection is
+    /// opened, rather than added to each statement, so they also apply to
+    /// [`Session::connection`] and do not affect DDL or `INSERT` statements.
     ///
     /// # Arguments
     ///
-    /// * `arg` - The argument to add (currently only `OutputFormat` is supported)
+    /// * `arg` - The argument to add (currently only `OutputFormat` and
+    ///   `FormatOptions` are supported)
     ///
     /// # Examples
     ///
@@ -158,9 +273,11 @@ impl<'a> SessionBuilder<'a> {
     /// # Ok::<(), chdb_rust::error::Error>(())
     /// ```
     pub fn with_arg(mut self, arg: Arg<'a>) -> Self {
-        // Only OutputFormat is supported with the new API
-        if let Some(fmt) = arg.as_output_format() {
-            self.default_format = fmt;
+        // Only OutputFormat and FormatOptions are supported with the new API
+        match arg {
+            Arg::OutputFormat(fmt) => self.default_format = fmt,
+            Arg::FormatOptions(options) => self.default_format_options = Some(options),
+            _ => {}
         }
         self
     }
@@ -187,14 +304,143 @@ impl<'a> SessionBuilder<'a> {
     /// # Ok::<(), chdb_rust::error::Error>(())
     /// ```
     pub fn with_auto_cleanup(mut self, value: bool) -> Self {
-        self.auto_cleanup = value;
+        self.auto_cleanup = Some(value);
+        self
+    }
+
+    /// Whether the data directory is removed with the session.
+    fn cleans_up(&self) -> bool {
+        self.auto_cleanup.unwrap_or(self.temporary)
+    }
+
+    /// Open the session in read-only mode.
+    ///
+    /// The connection is opened with ClickHouse's `readonly = 2` setting, so
+    /// the server rejects writes, DDL and `SYSTEM` statements while still
+    /// allowing settings to be changed per query. This is what enforces the
+    /// mode. On top of that, [`Session::execute`] and [`Session::insert_rows`]
+    /// reject statements that do not start with a read keyword, as well as
+    /// input holding several statements, with [`Error::ReadOnly`] before they
+    /// reach chDB, to give a clearer error.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - Whether to open the session read-only
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::error::Error;
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new()
+    ///     .with_data_path("/tmp/mydb")
+    ///     .read_only(true)
+    ///     .build()?;
+    ///
+    /// let err = session.execute("DROP TABLE events", None).unwrap_err();
+    /// assert!(matches!(err, Error::ReadOnly(_)));
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn read_only(mut self, value: bool) -> Self {
+        self.read_only = value;
+        self
+    }
+
+    /// Set how long [`build`](Self::build) waits for another process to
+    /// release the data directory.
+    ///
+    /// Sessions hold an advisory lock on their data directory until dropped,
+    /// so that two processes cannot open the same directory and corrupt it.
+    /// The lock is exclusive, except for [read-only](Self::read_only)
+    /// sessions, which share it with each other. By default, `build` fails
+    /// immediately if the directory is locked.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long to wait, or `None` to not lock the directory
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    ///
+    /// use chdb_rust::error::Error;
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// match SessionBuilder::new()
+    ///     .with_data_path("/tmp/mydb")
+    ///     .with_lock_timeout(Some(Duration::from_secs(5)))
+    ///     .build()
+    /// {
+    ///     Ok(session) => { /* ... */ }
+    ///     Err(Error::DataDirLocked { pid }) => eprintln!("in use by {pid:?}"),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn with_lock_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// Allow [`Session::backup_to`] and [`Session::restore_from`] to use
+    /// paths under `path`.
+    ///
+    /// ClickHouse only writes and reads `File()` backups under its
+    /// `backups.allowed_path` setting, which this sets when the connection is
+    /// opened. Without it, backups fail with a "not allowed" error.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The directory backups are stored under
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new()
+    ///     .with_data_path("/tmp/mydb")
+    ///     .with_backup_dir("/tmp/backups")
+    ///     .build()?;
+    /// session.backup_to("/tmp/backups/full", None)?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn with_backup_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.backup_dir = Some(path.into());
+        self
+    }
+
+    /// Capture chDB's internal logs at `level` and above.
+    ///
+    /// chDB logs to `chdb.log` in the data directory, and each record is
+    /// re-emitted through the `log` crate and/or as a `tracing` event under
+    /// the `chdb` target. See [`log_capture`](crate::log_capture).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::log_level::LogLevel;
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new()
+    ///     .with_data_path("/tmp/mydb")
+    ///     .with_log_capture(LogLevel::Info)
+    ///     .build()?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    pub fn with_log_capture(mut self, level: LogLevel) -> Self {
+        self.log_capture = Some(level);
         self
     }
 
     /// Build the session with the configured settings.
     ///
     /// This creates the data directory if it doesn't exist and establishes
-    /// a connection to the database.
+    /// a connection to the database. With auto cleanup enabled, a directory
+    /// created here is removed again if the connection cannot be opened.
     ///
     /// # Returns
     ///
@@ -206,6 +452,10 @@ impl<'a> SessionBuilder<'a> {
     /// Returns an error if:
     /// - The data path cannot be created
     /// - The data path has insufficient permissions
+    /// - The snapshot to start from does not exist
+    ///   ([`Error::SnapshotNotFound`]), or the data path is not empty
+    /// - Another process holds the data directory lock
+    ///   ([`Error::DataDirLocked`])
     /// - The connection cannot be established
     ///
     /// # Examples
@@ -218,23 +468,174 @@ impl<'a> SessionBuilder<'a> {
     ///     .build()?;
     /// # Ok::<(), chdb_rust::error::Error>(())
     /// ```
-    pub fn build(self) -> Result<Session, Error> {
+    pub fn build(mut self) -> Result<Session, Error> {
+        let mut created = create_dir(&self.data_path)?;
+        while self.temporary && !created {
+            // Something else already took the generated name.
+            self.data_path = temporary_data_path();
+            created = create_dir(&self.data_path)?;
+        }
+        let created = CreatedDir::new(&self.data_path, created && self.cleans_up());
         let data_path = self.data_path.to_str().ok_or(Error::PathError)?.to_string();
-
-        fs::create_dir_all(&self.data_path)?;
         if fs::metadata(&self.data_path)?.permissions().readonly() {
             return Err(Error::InsufficientPermissions);
         }
+        if let Some(name) = &self.snapshot {
+            snapshot::restore(name, &self.data_path)?;
+        }
+        let lock = self
+            .lock_timeout
+            .map(|timeout| DirLock::acquire(&self.data_path, self.read_only, timeout))
+            .transpose()?;
 
-        let conn = Connection::open_with_path(&data_path)?;
+        let args = self.connection_args(&data_path)?;
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        let (conn, log_capture) = self.open_with_log_capture(args)?;
+        #[cfg(not(any(feature = "log", feature = "tracing")))]
+        let conn = self.open(args)?;
+        created.keep();
 
         Ok(Session {
             conn,
             data_path,
             default_format: self.default_format,
-            auto_cleanup: self.auto_cleanup,
+            auto_cleanup: self.cleans_up(),
+            read_only: self.read_only,
+            _lock: lock,
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            log_capture,
         })
     }
+
+    /// The `clickhouse` arguments opening the session's connection.
+    fn connection_args(&self, data_path: &str) -> Result<Vec<String>, Error> {
+        let mut args = vec!["clickhouse".to_string(), format!("--path={data_path}")];
+        if let Some(options) = &self.default_format_options {
+            args.extend(options.to_args()?);
+        }
+        if self.read_only {
+            // Unlike `readonly = 1`, this still allows the `SETTINGS` clauses
+            // added by `query_with_options` and `query_with_id`.
+            args.push("--readonly=2".to_string());
+        }
+        Ok(args)
+    }
+
+    /// Open the connection, logging to a captured file if requested.
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    fn open_with_log_capture(
+        &self,
+        mut args: Vec<String>,
+    ) -> Result<(Connection, Option<LogCapture>), Error> {
+        let Some(level) = self.log_capture else {
+            return Ok((self.open(args)?, None));
+        };
+        let log_path = self.data_path.join(LOG_FILE_NAME);
+        args.push(format!(
+            "--log-file={}",
+            log_path.to_str().ok_or(Error::PathError)?
+        ));
+        args.push(format!("--log-level={}", level.as_str()));
+        let capture = LogCapture::start(&log_path)?;
+        let conn = self.open(args)?;
+        Ok((conn, Some(capture)))
+    }
+
+    /// Open the connection with `args`, followed by any server configuration.
+    fn open(&self, mut args: Vec<String>) -> Result<Connection, Error> {
+        if let Some(dir) = &self.backup_dir {
+            let dir = std::path::absolute(dir)?;
+            // Arguments after `--` override the server configuration.
+            args.push("--".to_string());
+            args.push(format!(
+                "--backups.allowed_path={}",
+                dir.to_str().ok_or(Error::PathError)?
+            ));
+        }
+        Connection::open(&args.iter().map(String::as_str).collect::<Vec<_>>())
+    }
+}
+
+/// A unique directory under the system's temporary directory.
+fn temporary_data_path() -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    std::env::temp_dir().join(format!(
+        "chdb-{}-{nanos:08x}-{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Create the directory `path` and its parents, returning whether `path`
+/// itself was created here rather than already existing.
+///
+/// The last step is a single `mkdir`, so of two builds racing on the same
+/// path only one sees it as created.
+fn create_dir(path: &Path) -> Result<bool, Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    match fs::create_dir(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && path.is_dir() => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Removes the data directory if [`SessionBuilder::build`] created it and
+/// then failed, or panicked, before handing it to a [`Session`].
+struct CreatedDir<'p> {
+    path: Option<&'p Path>,
+}
+
+impl<'p> CreatedDir<'p> {
+    /// Remove `path` on drop if `remove` is set, which it should only be for
+    /// a directory created by this build.
+    fn new(path: &'p Path, remove: bool) -> Self {
+        Self {
+            path: remove.then_some(path),
+        }
+    }
+
+    fn keep(mut self) {
+        self.path = None;
+    }
+}
+
+impl Drop for CreatedDir<'_> {
+    fn drop(&mut self) {
+        if let Some(path) = self.path {
+            fs::remove_dir_all(path).ok();
+        }
+    }
+}
+
+/// Statements allowed on a read-only session, by leading keyword.
+const READ_ONLY_STATEMENTS: &[&str] = &[
+    "SELECT", "WITH", "FROM", "SHOW", "DESCRIBE", "DESC", "EXPLAIN", "EXISTS", "SET", "USE",
+];
+
+/// The leading keyword of `sql`, uppercased, skipping whitespace, comments
+/// and opening parentheses.
+pub(crate) fn leading_keyword(sql: &str) -> String {
+    let mut rest = sql;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '(');
+        if let Some(comment) = rest.strip_prefix("--") {
+            rest = comment.split_once('\n').map_or("", |(_, after)| after);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.split_once("*/").map_or("", |(_, after)| after);
+        } else {
+            break;
+        }
+    }
+    rest.chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase()
 }
 
 impl Default for SessionBuilder<'_> {
@@ -290,19 +691,545 @@ impl Session {
     /// Returns an error if:
     /// - The query syntax is invalid
     /// - The query references non-existent tables or columns
+    /// - The session is [read-only](SessionBuilder::read_only) and the query
+    ///   is not a read
     /// - The query execution fails for any other reason
     pub fn execute(&self, query: &str, query_args: Option<&[Arg]>) -> Result<QueryResult, Error> {
+        self.check_writable(query)?;
         let fmt = query_args
             .and_then(|args| args.iter().find_map(|a| a.as_output_format()))
             .unwrap_or(self.default_format);
-        self.conn.query(query, fmt)
+        match extract_format_options(query_args) {
+            Some(options) => self.conn.query_with_options(query, fmt, options),
+            None => self.conn.query(query, fmt),
+        }
+    }
+
+    /// Whether the session was opened [read-only](SessionBuilder::read_only).
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Fail with [`Error::ReadOnly`] if `sql` may modify a read-only session.
+    ///
+    /// Only the leading keyword is inspected, so input holding several
+    /// statements is rejected as a whole.
+    fn check_writable(&self, sql: &str) -> Result<(), Error> {
+        if !self.read_only {
+            return Ok(());
+        }
+        if split_statements(sql).len() > 1 {
+            return Err(Error::ReadOnly("multiple statements".to_string()));
+        }
+        let keyword = leading_keyword(sql);
+        if READ_ONLY_STATEMENTS.contains(&keyword.as_str()) {
+            Ok(())
+        } else {
+            Err(Error::ReadOnly(keyword))
+        }
+    }
+
+    /// The directory the session stores its data in.
+    pub fn data_path(&self) -> &Path {
+        Path::new(&self.data_path)
+    }
+
+    /// Get the underlying [`Connection`] of this session.
+    ///
+    /// This gives access to connection-level APIs such as streaming and
+    /// exporting results to files.
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Explain how a query would be executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `sql` - The query to explain
+    /// * `kind` - The `EXPLAIN` variant; see [`ExplainKind`]
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::explain::ExplainKind;
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new().with_data_path("/tmp/mydb").build()?;
+    /// let explain = session.explain("SELECT * FROM events WHERE id = 42", ExplainKind::Plan)?;
+    /// let read = explain.plan().unwrap().find("ReadFromMergeTree").unwrap();
+    /// for index in &read.indexes {
+    ///     println!("{}: {:?} -> {:?} granules", index.kind, index.initial_granules, index.selected_granules);
+    /// }
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query cannot be explained, or its output
+    /// cannot be parsed.
+    pub fn explain(&self, sql: &str, kind: ExplainKind) -> Result<Explain, Error> {
+        let result = self
+            .conn
+            .query(&kind.to_sql(sql), OutputFormat::TabSeparatedRaw)?;
+        Explain::parse(kind, &result.data_utf8_lossy())
+    }
+
+    /// Save a copy of the session's data directory as the snapshot `name`,
+    /// replacing any previous snapshot of that name.
+    ///
+    /// Background merges are paused while copying. Use
+    /// [`SessionBuilder::from_snapshot`] to start sessions from it.
+    ///
+    /// # Returns
+    ///
+    /// Returns the directory the snapshot was saved in.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new().temporary().build()?;
+    /// session.execute("CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id", None)?;
+    /// session.execute("INSERT INTO t SELECT number FROM numbers(1000)", None)?;
+    /// session.snapshot("fixture")?;
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `name` is not a plain file name, or the data
+    /// directory cannot be copied.
+    pub fn snapshot(&self, name: &str) -> Result<PathBuf, Error> {
+        if self.read_only {
+            return snapshot::save(self.data_path(), name);
+        }
+        self.conn
+            .query("SYSTEM STOP MERGES", OutputFormat::TabSeparated)?;
+        let saved = snapshot::save(self.data_path(), name);
+        self.conn
+            .query("SYSTEM START MERGES", OutputFormat::TabSeparated)?;
+        saved
+    }
+
+    /// Back up the session's data to a local directory, or a `.zip` archive
+    /// if `path` ends in `.zip`, with `BACKUP ... TO File(...)`.
+    ///
+    /// `path` must be under the session's
+    /// [backup directory](SessionBuilder::with_backup_dir).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - Where to write the backup; it must not exist yet
+    /// * `options` - The tables or databases to back up and an optional base
+    ///   backup, or `None` to back up all databases in full
+    ///
+    /// # Returns
+    ///
+    /// Returns the finished backup, or a running one if the options are
+    /// [asynchronous](BackupOptions::asynchronous).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::backup::BackupOptions;
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new()
+    ///     .with_data_path("/tmp/mydb")
+    ///     .with_backup_dir("/tmp/backups")
+    ///     .build()?;
+    /// let options = BackupOptions::new().with_database("analytics");
+    /// let info = session.backup_to("/tmp/backups/analytics.zip", Some(&options))?;
+    /// println!("{}: {} files, {} bytes", info.status, info.num_files, info.total_size);
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the backup fails, e.g. because `path` already
+    /// exists or a table does not.
+    pub fn backup_to(
+        &self,
+        path: impl AsRef<Path>,
+        options: Option<&BackupOptions>,
+    ) -> Result<BackupInfo, Error> {
+        let sql = options
+            .cloned()
+            .unwrap_or_default()
+            .backup_sql(path.as_ref())?;
+        self.run_backup(&sql)
+    }
+
+    /// Restore a backup written by [`backup_to`](Self::backup_to) with
+    /// `RESTORE ... FROM File(...)`.
+    ///
+    /// Tables that already exist are not overwritten, so drop them first.
+    /// Files kept in the base of an incremental backup are read from it.
+    /// Like in [`backup_to`](Self::backup_to), `path` must be under the
+    /// session's [backup directory](SessionBuilder::with_backup_dir).
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The backup to restore
+    /// * `options` - The tables or databases to restore, or `None` for all
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new()
+    ///     .temporary()
+    ///     .with_backup_dir("/tmp/backups")
+    ///     .build()?;
+    /// let info = session.restore_from("/tmp/backups/analytics.zip", None)?;
+    /// assert!(info.status.is_success());
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] on a read-only session, or an error if the
+    /// restore fails, e.g. because a restored table already exists.
+    pub fn restore_from(
+        &self,
+        path: impl AsRef<Path>,
+        options: Option<&BackupOptions>,
+    ) -> Result<BackupInfo, Error> {
+        self.check_writable("RESTORE")?;
+        let sql = options
+            .cloned()
+            .unwrap_or_default()
+            .restore_sql(path.as_ref())?;
+        self.run_backup(&sql)
+    }
+
+    /// Look up a backup or restore started by this process, e.g. to follow
+    /// the progress of an [asynchronous](BackupOptions::asynchronous) one.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if there is no operation with the id `id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `system.backups` cannot be read.
+    pub fn backup_status(&self, id: &str) -> Result<Option<BackupInfo>, Error> {
+        let result = self
+            .conn
+            .query(&BackupInfo::select_sql(id), OutputFormat::JSONEachRow)?;
+        result
+            .data_utf8_lossy()
+            .lines()
+            .next()
+            .map(BackupInfo::parse)
+            .transpose()
+    }
+
+    fn run_backup(&self, sql: &str) -> Result<BackupInfo, Error> {
+        let result = self.conn.query(sql, OutputFormat::TabSeparated)?;
+        let id = BackupInfo::parse_id(&result.data_utf8_lossy())?;
+        self.backup_status(&id)?
+            .ok_or_else(|| Error::InvalidData(format!("Backup {id} is not in system.backups")))
+    }
+
+    /// Look up a query run with
+    /// [`Connection::query_with_id`](crate::connection::Connection::query_with_id)
+    /// in `system.query_log`.
+    ///
+    /// Pending log entries are flushed first, except on a
+    /// [read-only](SessionBuilder::read_only) session, which cannot run
+    /// `SYSTEM FLUSH LOGS`: there, a query only shows up once chDB flushes
+    /// its logs by itself, every few seconds. If the id was used more than
+    /// once, the latest query is returned.
+    ///
+    /// # Returns
+    ///
+    /// Returns `None` if no finished query is tagged with `query_id`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::format::OutputFormat;
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new().with_data_path("/tmp/mydb").build()?;
+    /// session
+    ///     .connection()
+    ///     .query_with_id("SELECT sum(number) FROM numbers(1000)", OutputFormat::CSV, Some("sum"))?;
+    ///
+    /// let entry = session.query_log("sum")?.expect("query was logged");
+    /// println!("read {} rows in {:?}", entry.read_rows, entry.duration);
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the logs cannot be flushed or `system.query_log`
+    /// cannot be read, e.g. because query logging is disabled.
+    pub fn query_log(&self, query_id: &str) -> Result<Option<QueryLogEntry>, Error> {
+        if !self.read_only {
+            self.conn
+                .query("SYSTEM FLUSH LOGS", OutputFormat::TabSeparated)?;
+        }
+        let result = self.conn.query(
+            &QueryLogEntry::select_sql(query_id),
+            OutputFormat::RowBinary,
+        )?;
+        let mut buf = result.data_ref();
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        QueryLogEntry::decode(&mut buf).map(Some)
+    }
+
+    /// Start a query over a local file, or files matching a glob pattern.
+    ///
+    /// The returned [`Select`] reads from `file()` and can be refined with
+    /// filters, projections and limits before being executed on this session.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The file path or glob pattern, e.g. `logs/*.csv.gz`
+    /// * `format` - The input format, or `None` to infer it from the extension,
+    ///   or to let ClickHouse detect it if the extension is not a known one
+    /// * `schema` - The structure, e.g. `id UInt64, msg String`, or `None` to let
+    ///   ClickHouse infer it
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use chdb_rust::arg::Arg;
+    /// use chdb_rust::format::{InputFormat, OutputFormat};
+    /// use chdb_rust::session::SessionBuilder;
+    ///
+    /// let session = SessionBuilder::new()
+    ///     .with_data_path("/tmp/mydb")
+    ///     .with_auto_cleanup(true)
+    ///     .build()?;
+    ///
+    /// let result = session
+    ///     .read_file("tests/logs.csv", Some(InputFormat::CSV), Some("id UInt64, msg String"))?
+    ///     .filter("id > ?", [0])
+    ///     .execute(&session, Some(&[Arg::OutputFormat(OutputFormat::JSONEachRow)]))?;
+    /// println!("{}", result.data_utf8_lossy());
+    /// # Ok::<(), chdb_rust::error::Error>(())
+    /// ```
+    pub fn read_file(
+        &self,
+        path: impl AsRef<Path>,
+        format: Option<InputFormat>,
+        schema: Option<&str>,
+    ) -> Result<Select, Error> {
+        let mut source = TableFunction::file(path);
+        if let Some(format) = format {
+            source = source.format(format);
+        }
+        if let Some(schema) = schema {
+            source = source.structure(schema);
+        }
+        Select::from_table_function(&source)
+    }
+
+    /// Infer the schema of a local file with `DESCRIBE file(...)`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or ClickHouse cannot
+    /// detect its format.
+    pub fn infer_schema(&self, path: impl AsRef<Path>) -> Result<Vec<ColumnSchema>, Error> {
+        self.describe(&TableFunction::file(path))
+    }
+
+    /// Describe the columns of any table function with `DESCRIBE`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `DESCRIBE` query fails.
+    pub fn describe(&self, source: &TableFunction) -> Result<Vec<ColumnSchema>, Error> {
+        let result = self.conn.query(
+            &format!("DESCRIBE {}", source.to_sql()?),
+            OutputFormat::TabSeparatedRaw,
+        )?;
+        Ok(ColumnSchema::parse_describe(&result.data_utf8_lossy()))
+    }
+
+    /// Execute a query and decode its result into typed rows.
+    ///
+    /// The query is executed with the `RowBinary` output format, so the
+    /// selected columns must match the field order and types of `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// #[derive(chdb_rust::ChdbRow)]
+    /// struct User {
+    ///     id: u64,
+    ///     name: String,
+    /// }
+    ///
+    /// let users: Vec<User> = session.query_rows("SELECT id, name FROM users")?;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or its output cannot be decoded as `T`.
+    pub fn query_rows<T: Row>(&self, query: &str) -> Result<Vec<T>, Error> {
+        let result = self.conn.query(query, OutputFormat::RowBinary)?;
+        decode_rows(result.data_ref())
+    }
+
+    /// Insert typed rows into a table.
+    ///
+    /// Rows are encoded as `RowBinary` and inserted through the `format()`
+    /// table function, in chunks for large inputs. Each statement must fit in
+    /// ClickHouse's `max_query_size`, 256 KiB by default, so a single row
+    /// whose encoding does not fit is rejected.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ReadOnly`] on a read-only session,
+    /// [`Error::InvalidData`] if a row is too large for one statement, or an
+    /// error if any of the `INSERT` statements fails. Chunks inserted before
+    /// the failure are not rolled back.
+    pub fn insert_rows<T: Row>(&self, table: &str, rows: &[T]) -> Result<(), Error> {
+        self.check_writable("INSERT")?;
+        let instrument = Instrument::insert(table, rows.len());
+        let result = instrument.in_scope(|| self.insert_row_chunks(table, rows));
+        instrument.finish(&result);
+        result
+    }
+
+    fn insert_row_chunks<T: Row>(&self, table: &str, rows: &[T]) -> Result<(), Error> {
+        let columns = T::COLUMNS
+            .iter()
+            .map(|c| quote_column(c))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let prefix = format!(
+            "INSERT INTO {} ({columns}) SELECT * FROM format(RowBinary, {}, ",
+            quote_identifier(table),
+            quote_literal(&T::structure()),
+        );
+
+        let mut buf = Vec::new();
+        let mut first_row = 0;
+        for (i, row) in rows.iter().enumerate() {
+            let start = buf.len();
+            row.encode_row(&mut buf);
+            if start > 0 && buf.len() > INSERT_CHUNK_SIZE {
+                // Send the rows before this one, which starts the next chunk.
+                let next = buf.split_off(start);
+                self.insert_chunk(&prefix, &buf, first_row)?;
+                buf = next;
+                first_row = i;
+            }
+        }
+        if !buf.is_empty() {
+            self.insert_chunk(&prefix, &buf, first_row)?;
+        }
+        Ok(())
+    }
+
+    /// Insert one chunk of encoded rows, starting at row `first_row`.
+    fn insert_chunk(&self, prefix: &str, payload: &[u8], first_row: usize) -> Result<(), Error> {
+        let sql = format!("{prefix}{})", quote_bytes(payload));
+        // Chunks only outgrow the limit when they hold a single large row.
+        if sql.len() > DEFAULT_MAX_QUERY_SIZE {
+            let max_query_size = self
+                .conn
+                .query(
+                    "SELECT getSetting('max_query_size')",
+                    OutputFormat::TabSeparated,
+                )?
+                .data_utf8_lossy()
+                .trim()
+                .parse::<usize>()
+                .unwrap_or(DEFAULT_MAX_QUERY_SIZE);
+            if sql.len() > max_query_size {
+                return Err(Error::InvalidData(format!(
+                    "Row {first_row} needs a {} byte INSERT statement, over the \
+                     max_query_size of {max_query_size} bytes",
+                    sql.len()
+                )));
+            }
+        }
+        self.conn.query(&sql, self.default_format)?;
+        Ok(())
     }
 }
 
 impl Drop for Session {
     fn drop(&mut self) {
+        // Drain the remaining log records before the log file can be removed.
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        drop(self.log_capture.take());
         if self.auto_cleanup {
             fs::remove_dir_all(&self.data_path).ok();
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temporary_data_path() {
+        let a = temporary_data_path();
+        let b = temporary_data_path();
+        assert_ne!(a, b);
+        assert!(a.starts_with(std::env::temp_dir()));
+    }
+
+    #[test]
+    fn test_created_dir_removed_unless_kept() {
+        let path = temporary_data_path();
+        let created = create_dir(&path).unwrap();
+        drop(CreatedDir::new(&path, created));
+        assert!(!path.exists());
+
+        let created = create_dir(&path).unwrap();
+        CreatedDir::new(&path, created).keep();
+        assert!(path.exists());
+        let created = create_dir(&path).unwrap();
+        assert!(!created, "existing directories are not created here");
+        drop(CreatedDir::new(&path, created));
+        assert!(path.exists());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn test_temporary_cleanup() {
+        assert!(!SessionBuilder::new().cleans_up());
+        assert!(SessionBuilder::new().temporary().cleans_up());
+        assert!(SessionBuilder::new().from_snapshot("s").cleans_up());
+        assert!(!SessionBuilder::new()
+            .temporary()
+            .with_auto_cleanup(false)
+            .cleans_up());
+        assert!(!SessionBuilder::new()
+            .temporary()
+            .with_data_path("/srv/db")
+            .cleans_up());
+        assert!(!SessionBuilder::new()
+            .from_snapshot("s")
+            .with_data_path("/srv/db")
+            .cleans_up());
+        assert!(SessionBuilder::new()
+            .with_auto_cleanup(true)
+            .temporary()
+            .with_data_path("/tmp/db")
+            .cleans_up());
+    }
+
+    #[test]
+    fn test_leading_keyword() {
+        assert_eq!(leading_keyword("select 1"), "SELECT");
+        assert_eq!(leading_keyword("  -- note\n/* x */ (SELECT 1)"), "SELECT");
+        assert_eq!(leading_keyword("\tinsert into t values (1)"), "INSERT");
+        assert_eq!(leading_keyword("-- only a comment"), "");
+    }
+}
@@ -0,0 +1,299 @@
+//! Named snapshots of session data directories, for test fixtures.
+//!
+//! [`Session::snapshot`] saves a copy of a session's data directory under a
+//! name, and [`SessionBuilder::from_snapshot`] starts a
+//! [temporary](crate::session::SessionBuilder::temporary) session from it,
+//! so that each test can start from a prepared dataset without re-creating
+//! it. Snapshots are stored in `chdb-snapshots` under the system's temporary
+//! directory and outlive the process that made them.
+//!
+//! MergeTree data parts are immutable, so they are hardlinked rather than
+//! copied where the filesystem allows it; everything else is copied.
+//!
+//! Saving, restoring and removing a snapshot are safe to run concurrently,
+//! from several threads or processes: a snapshot is copied to a private
+//! staging directory first and only swapped in under an exclusive lock,
+//! while restores hold a shared lock for as long as they copy.
+//!
+//! [`Session::snapshot`]: crate::session::Session::snapshot
+//! [`SessionBuilder::from_snapshot`]: crate::session::SessionBuilder::from_snapshot
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::session::SessionBuilder;
+//! use chdb_rust::snapshot;
+//!
+//! if !snapshot::exists("users") {
+//!     let session = SessionBuilder::new().temporary().build()?;
+//!     session.execute(
+//!         "CREATE TABLE users (id UInt64, name String) ENGINE = MergeTree ORDER BY id",
+//!         None,
+//!     )?;
+//!     session.execute("INSERT INTO users SELECT number, toString(number) FROM numbers(1000000)", None)?;
+//!     session.snapshot("users")?;
+//! }
+//!
+//! // Each test gets its own copy, removed when the session is dropped.
+//! let session = SessionBuilder::new().from_snapshot("users").build()?;
+//! session.execute("DELETE FROM users WHERE id < 10", None)?;
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::dir_lock::{DirLock, LOCK_FILE_NAME};
+use crate::error::{Error, Result};
+
+/// How long to wait for another thread or process using a snapshot.
+const LOCK_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Files of a running session that must not be carried into a snapshot.
+const EXCLUDED_FILES: &[&str] = &[LOCK_FILE_NAME, "status", "chdb.log"];
+
+/// The directory snapshots are stored in.
+pub fn snapshots_dir() -> PathBuf {
+    std::env::temp_dir().join("chdb-snapshots")
+}
+
+/// The directory of the snapshot `name`.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidData`] if `name` is empty or is not a plain file
+/// name, e.g. contains a path separator.
+pub fn snapshot_path(name: &str) -> Result<PathBuf> {
+    let plain = Path::new(name).file_name().and_then(|n| n.to_str()) == Some(name);
+    if !plain || name.starts_with('.') {
+        return Err(Error::InvalidData(format!(
+            "Invalid snapshot name '{name}'"
+        )));
+    }
+    Ok(snapshots_dir().join(name))
+}
+
+/// Whether the snapshot `name` exists.
+pub fn exists(name: &str) -> bool {
+    snapshot_path(name).is_ok_and(|path| path.is_dir())
+}
+
+/// Delete the snapshot `name`, if it exists.
+///
+/// # Errors
+///
+/// Returns an error if `name` is invalid or the snapshot cannot be removed.
+pub fn remove(name: &str) -> Result<()> {
+    let path = snapshot_path(name)?;
+    let _lock = lock(name, false)?;
+    remove_dir(&path)
+}
+
+fn remove_dir(path: &Path) -> Result<()> {
+    match fs::remove_dir_all(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Lock the snapshot `name`: shared to read it, exclusive to replace it.
+fn lock(name: &str, shared: bool) -> Result<DirLock> {
+    fs::create_dir_all(snapshots_dir())?;
+    let path = snapshots_dir().join(format!(".{name}.lock"));
+    DirLock::acquire_file(&path, shared, LOCK_TIMEOUT)
+}
+
+/// A staging directory for the snapshot `name`, unique to this call.
+fn staging_path(name: &str) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    snapshots_dir().join(format!(
+        ".{name}.{}.{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+/// Save `data_path` as the snapshot `name`, replacing any previous one.
+pub(crate) fn save(data_path: &Path, name: &str) -> Result<PathBuf> {
+    let path = snapshot_path(name)?;
+    // Copy next to the destination first, so that a failed copy leaves any
+    // previous snapshot intact and the lock is only held for the swap.
+    let staging = staging_path(name);
+    if let Err(e) = copy_tree(data_path, &staging, data_path, &staging) {
+        fs::remove_dir_all(&staging).ok();
+        return Err(e.into());
+    }
+    let swapped = lock(name, false).and_then(|_lock| {
+        remove_dir(&path)?;
+        fs::rename(&staging, &path)?;
+        Ok(())
+    });
+    if swapped.is_err() {
+        fs::remove_dir_all(&staging).ok();
+    }
+    swapped.map(|()| path)
+}
+
+/// Copy the snapshot `name` into the empty directory `data_path`.
+pub(crate) fn restore(name: &str, data_path: &Path) -> Result<()> {
+    let path = snapshot_path(name)?;
+    let _lock = lock(name, true)?;
+    if !path.is_dir() {
+        return Err(Error::SnapshotNotFound(name.to_string()));
+    }
+    if fs::read_dir(data_path)?.next().is_some() {
+        return Err(Error::InvalidData(format!(
+            "Cannot restore snapshot '{name}' into non-empty {}",
+            data_path.display()
+        )));
+    }
+    copy_tree(&path, data_path, &path, data_path)?;
+    Ok(())
+}
+
+/// Copy `src` into `dst`, hardlinking data part files. Symlinks pointing into
+/// the `src_root` tree are re-pointed into the `dst_root` tree.
+fn copy_tree(src: &Path, dst: &Path, src_root: &Path, dst_root: &Path) -> io::Result<()> {
+    fs::create_dir_all(dst)?;
+    // A MergeTree part directory always contains its checksums.
+    let is_part = src.join("checksums.txt").is_file();
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let from = entry.path();
+        let to = dst.join(&name);
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&from, &to, src_root, dst_root)?;
+        } else if file_type.is_dir() {
+            // Parts still being written or merged.
+            if !name.to_string_lossy().starts_with("tmp_") {
+                copy_tree(&from, &to, src_root, dst_root)?;
+            }
+        } else if !EXCLUDED_FILES.iter().any(|excluded| name == *excluded) {
+            link_or_copy(&from, &to, is_part)?;
+        }
+    }
+    Ok(())
+}
+
+/// Hardlink `from` if `link` is set and the filesystem allows it, or copy it.
+fn link_or_copy(from: &Path, to: &Path, link: bool) -> io::Result<()> {
+    if link && fs::hard_link(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to).map(drop)
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path, src_root: &Path, dst_root: &Path) -> io::Result<()> {
+    let target = fs::read_link(from)?;
+    let target = match target.strip_prefix(src_root) {
+        Ok(relative) => dst_root.join(relative),
+        Err(_) => target,
+    };
+    std::os::unix::fs::symlink(target, to)
+}
+
+/// chDB recreates the symlinks of its data directory when it opens it.
+#[cfg(not(unix))]
+fn copy_symlink(_from: &Path, _to: &Path, _src_root: &Path, _dst_root: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_path() {
+        assert_eq!(
+            snapshot_path("users").unwrap(),
+            snapshots_dir().join("users")
+        );
+        for name in ["", ".", "..", "a/b", ".hidden"] {
+            assert!(snapshot_path(name).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn test_concurrent_save_and_restore() {
+        let tmp = tempdir::TempDir::new("chdb-snapshot").unwrap();
+        let name = format!("test-concurrent-{}", std::process::id());
+        let sources: Vec<_> = (0..4)
+            .map(|i| {
+                let src = tmp.path().join(format!("src{i}"));
+                fs::create_dir_all(src.join("store")).unwrap();
+                for file in 0..20 {
+                    fs::write(src.join(format!("store/{file}.bin")), i.to_string()).unwrap();
+                }
+                src
+            })
+            .collect();
+        save(&sources[0], &name).unwrap();
+
+        std::thread::scope(|scope| {
+            for src in &sources {
+                scope.spawn(|| save(src, &name).unwrap());
+            }
+            for i in 0..4 {
+                let dst = tmp.path().join(format!("dst{i}"));
+                let name = &name;
+                scope.spawn(move || {
+                    fs::create_dir_all(&dst).unwrap();
+                    restore(name, &dst).unwrap();
+                    // A restore sees one complete version.
+                    let contents: Vec<_> = (0..20)
+                        .map(|file| fs::read(dst.join(format!("store/{file}.bin"))).unwrap())
+                        .collect();
+                    assert!(contents.iter().all(|c| *c == contents[0]));
+                });
+            }
+        });
+        remove(&name).unwrap();
+        assert!(!exists(&name));
+        let leftovers: Vec<_> = fs::read_dir(snapshots_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let file_name = entry.file_name();
+                let file_name = file_name.to_string_lossy();
+                file_name.starts_with(&format!(".{name}.")) && !file_name.ends_with(".lock")
+            })
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn test_copy_tree() {
+        let tmp = tempdir::TempDir::new("chdb-snapshot").unwrap();
+        let src = tmp.path().join("src");
+        let part = src.join("store/abc/all_1_1_0");
+        fs::create_dir_all(&part).unwrap();
+        fs::create_dir_all(src.join("store/abc/tmp_merge_all_1_2_1")).unwrap();
+        fs::write(part.join("checksums.txt"), "c").unwrap();
+        fs::write(part.join("data.bin"), "d").unwrap();
+        fs::write(src.join("status"), "pid").unwrap();
+        fs::write(src.join("metadata.sql"), "m").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(src.join("store/abc"), src.join("link")).unwrap();
+
+        let dst = tmp.path().join("dst");
+        copy_tree(&src, &dst, &src, &dst).unwrap();
+        assert_eq!(
+            fs::read(dst.join("store/abc/all_1_1_0/data.bin")).unwrap(),
+            b"d"
+        );
+        assert_eq!(fs::read(dst.join("metadata.sql")).unwrap(), b"m");
+        assert!(!dst.join("status").exists());
+        assert!(!dst.join("store/abc/tmp_merge_all_1_2_1").exists());
+        #[cfg(unix)]
+        assert_eq!(
+            fs::read_link(dst.join("link")).unwrap(),
+            dst.join("store/abc")
+        );
+    }
+}
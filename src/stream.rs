@@ -0,0 +1,145 @@
+//! Streaming query results.
+//!
+//! This module provides [`QueryStream`], which fetches the output of a query
+//! chunk by chunk instead of materializing it in memory all at once. Streams
+//! are created with [`Connection::query_stream`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::connection::Connection;
+//! use chdb_rust::format::OutputFormat;
+//!
+//! let conn = Connection::open_in_memory()?;
+//! let stream = conn.query_stream("SELECT number FROM numbers(10000000)", OutputFormat::CSV)?;
+//! for chunk in stream {
+//!     let chunk = chunk?;
+//!     println!("Received {} bytes", chunk.data_ref().len());
+//! }
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::time::Instant;
+
+use crate::bindings;
+use crate::connection::Connection;
+use crate::error::{Error, Result};
+use crate::instrument::{Instrument, Stats};
+use crate::query_result::{check_raw_error, QueryResult};
+
+/// An in-progress streaming query.
+///
+/// Iterating yields one [`QueryResult`] per chunk until the query finishes.
+/// Dropping the stream before it is exhausted cancels the query.
+#[derive(Debug)]
+pub struct QueryStream<'a> {
+    conn: &'a Connection,
+    inner: *mut bindings::chdb_result,
+    finished: bool,
+    // Taken once the outcome has been recorded.
+    instrument: Option<Instrument>,
+    stats: Stats,
+    start: Instant,
+}
+
+impl<'a> QueryStream<'a> {
+    /// Take ownership of a streaming handle returned by `chdb_stream_query`.
+    pub(crate) fn new(
+        conn: &'a Connection,
+        inner: *mut bindings::chdb_result,
+        instrument: Instrument,
+    ) -> Result<Self> {
+        if inner.is_null() {
+            instrument.fail(&Error::NoResult);
+            return Err(Error::NoResult);
+        }
+        // Construct first so the handle is released even if the query failed to start.
+        let mut stream = Self {
+            conn,
+            inner,
+            finished: false,
+            instrument: Some(instrument),
+            stats: Stats::default(),
+            start: Instant::now(),
+        };
+        if let Err(e) = check_raw_error(inner) {
+            stream.fail(&e);
+            return Err(e);
+        }
+        Ok(stream)
+    }
+
+    fn fail(&mut self, error: &Error) {
+        if let Some(instrument) = self.instrument.take() {
+            instrument.fail(error);
+        }
+    }
+
+    /// Record the outcome of a stream that ended without an error.
+    fn finish(&mut self, cancelled: bool) {
+        if let Some(instrument) = self.instrument.take() {
+            let stats = Stats {
+                elapsed_secs: self.start.elapsed().as_secs_f64(),
+                ..self.stats
+            };
+            if cancelled {
+                instrument.cancel(stats);
+            } else {
+                instrument.succeed(stats);
+            }
+        }
+    }
+
+    /// Cancel the query. Further calls to `next` return `None`.
+    pub fn cancel(&mut self) {
+        if !self.finished {
+            unsafe { bindings::chdb_stream_cancel_query(self.conn.raw(), self.inner) };
+            self.finished = true;
+            self.finish(true);
+        }
+    }
+}
+
+impl Iterator for QueryStream<'_> {
+    type Item = Result<QueryResult>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let chunk_ptr = match &self.instrument {
+            Some(instrument) => instrument.in_scope(|| unsafe {
+                bindings::chdb_stream_fetch_result(self.conn.raw(), self.inner)
+            }),
+            None => unsafe { bindings::chdb_stream_fetch_result(self.conn.raw(), self.inner) },
+        };
+        if chunk_ptr.is_null() {
+            self.finished = true;
+            self.fail(&Error::NoResult);
+            return Some(Err(Error::NoResult));
+        }
+
+        let chunk = QueryResult::new(chunk_ptr);
+        if let Err(e) = chunk.check_error_ref() {
+            self.finished = true;
+            self.fail(&e);
+            return Some(Err(e));
+        }
+        if chunk.data_ref().is_empty() {
+            self.finished = true;
+            self.finish(false);
+            return None;
+        }
+        self.stats.add(&chunk);
+        Some(Ok(chunk))
+    }
+}
+
+impl Drop for QueryStream<'_> {
+    fn drop(&mut self) {
+        // Cancels the query, and records it as such, unless it already ended.
+        self.cancel();
+        unsafe { bindings::chdb_destroy_query_result(self.inner) };
+    }
+}
@@ -0,0 +1,266 @@
+//! Typed helpers for ClickHouse table functions.
+//!
+//! This module provides [`TableFunction`] for reading external data through
+//! the `file()`, `url()` and `s3()` table functions without writing their SQL
+//! by hand. Paths and URLs are quoted as string literals, glob patterns such
+//! as `logs/*.csv` or `data_{1..3}.parquet` are passed through to ClickHouse,
+//! and the [`InputFormat`] is inferred from the extension when not given, or
+//! left to ClickHouse to detect for extensions this crate does not know.
+//! Compressed inputs such as `events.ndjson.gz` are decompressed transparently;
+//! the method is detected from the extension or set with
+//! [`TableFunction::compression`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use chdb_rust::format::InputFormat;
+//! use chdb_rust::query_builder::Select;
+//! use chdb_rust::session::SessionBuilder;
+//! use chdb_rust::table_function::TableFunction;
+//!
+//! let session = SessionBuilder::new()
+//!     .with_data_path("/tmp/mydb")
+//!     .with_auto_cleanup(true)
+//!     .build()?;
+//!
+//! // Read a CSV file, inferring the format from the extension.
+//! let result = session
+//!     .read_file("tests/logs.csv", None, None)?
+//!     .limit(10)
+//!     .execute(&session, None)?;
+//!
+//! // Query every Parquet file in a directory with an explicit schema.
+//! let source = TableFunction::file("data/*.parquet")
+//!     .format(InputFormat::Parquet)
+//!     .structure("id UInt64, name String");
+//! let result = Select::from_table_function(&source)?
+//!     .columns(["name"])
+//!     .execute(&session, None)?;
+//! # Ok::<(), chdb_rust::error::Error>(())
+//! ```
+
+use std::path::Path;
+
+use crate::error::Result;
+use crate::format::{Compression, InputFormat};
+use crate::query_builder::quote_literal;
+
+#[derive(Debug, Clone)]
+enum Source {
+    File,
+    Url,
+    S3 {
+        credentials: Option<(String, String)>,
+    },
+}
+
+/// A `file()`, `url()` or `s3()` table function.
+#[derive(Debug, Clone)]
+pub struct TableFunction {
+    source: Source,
+    location: String,
+    format: Option<InputFormat>,
+    structure: Option<String>,
+    compression: Option<Compression>,
+}
+
+impl TableFunction {
+    fn new(source: Source, location: String) -> Self {
+        Self {
+            source,
+            location,
+            format: None,
+            structure: None,
+            compression: None,
+        }
+    }
+
+    /// Read a local file, or files matching a glob pattern, with `file()`.
+    ///
+    /// Relative paths are resolved by chDB against the current directory.
+    pub fn file(path: impl AsRef<Path>) -> Self {
+        Self::new(Source::File, path.as_ref().to_string_lossy().into_owned())
+    }
+
+    /// Read files matching `pattern` inside `dir`, e.g. `glob("logs", "*.csv")`.
+    pub fn glob(dir: impl AsRef<Path>, pattern: &str) -> Self {
+        Self::file(dir.as_ref().join(pattern))
+    }
+
+    /// Read data over HTTP(S) with `url()`.
+    pub fn url(url: impl Into<String>) -> Self {
+        Self::new(Source::Url, url.into())
+    }
+
+    /// Read objects from S3-compatible storage with `s3()`.
+    ///
+    /// Without [`credentials`](Self::credentials) the bucket is accessed
+    /// anonymously or with the environment's default credentials.
+    pub fn s3(url: impl Into<String>) -> Self {
+        Self::new(Source::S3 { credentials: None }, url.into())
+    }
+
+    /// Set the input format instead of inferring it from the extension.
+    pub fn format(mut self, format: InputFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set the structure, e.g. `id UInt64, name String`.
+    ///
+    /// When omitted, ClickHouse infers the schema from the data.
+    pub fn structure(mut self, structure: impl Into<String>) -> Self {
+        self.structure = Some(structure.into());
+        self
+    }
+
+    /// Set the compression method of the input instead of detecting it from
+    /// the extension, e.g. for a gzip stream served from a URL without `.gz`.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Set S3 access credentials. Has no effect on `file()` and `url()`.
+    pub fn credentials(mut self, access_key_id: &str, secret_access_key: &str) -> Self {
+        if let Source::S3 { credentials } = &mut self.source {
+            *credentials = Some((access_key_id.to_string(), secret_access_key.to_string()));
+        }
+        self
+    }
+
+    /// The path or URL read by this table function.
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// The explicit or inferred input format, or `None` if no format was set
+    /// and the location's extension is not a known one, in which case
+    /// ClickHouse detects the format itself.
+    pub fn input_format(&self) -> Option<InputFormat> {
+        let location = self.location.split(['?', '#']).next().unwrap_or_default();
+        self.format.or_else(|| InputFormat::from_path(location))
+    }
+
+    /// Render the table function call, e.g. `file('data.csv', 'CSV')`.
+    pub fn to_sql(&self) -> Result<String> {
+        let mut args = vec![quote_literal(&self.location)];
+        if let Source::S3 {
+            credentials: Some((key, secret)),
+        } = &self.source
+        {
+            args.push(quote_literal(key));
+            args.push(quote_literal(secret));
+        }
+        match self.input_format() {
+            Some(format) => args.push(quote_literal(format.as_str())),
+            // Later arguments are positional, so the format must be given.
+            None if self.structure.is_some() || self.compression.is_some() => {
+                args.push(quote_literal("auto"));
+            }
+            None => {}
+        }
+        if let Some(structure) = &self.structure {
+            args.push(quote_literal(structure));
+        }
+        if let Some(compression) = self.compression {
+            if self.structure.is_none() {
+                args.push(quote_literal("auto"));
+            }
+            args.push(quote_literal(compression.as_str()));
+        }
+
+        let name = match self.source {
+            Source::File => "file",
+            Source::Url => "url",
+            Source::S3 { .. } => "s3",
+        };
+        Ok(format!("{name}({})", args.join(", ")))
+    }
+}
+
+/// A column reported by schema inference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSchema {
+    /// Column name.
+    pub name: String,
+    /// Inferred ClickHouse type.
+    pub ty: String,
+}
+
+impl ColumnSchema {
+    /// Parse the `TabSeparated` output of a `DESCRIBE` query.
+    pub(crate) fn parse_describe(output: &str) -> Vec<Self> {
+        output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(Self {
+                    name: fields.next()?.to_string(),
+                    ty: fields.next()?.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_infers_format() {
+        assert_eq!(
+            TableFunction::file("data/it's.csv").to_sql().unwrap(),
+            "file('data/it\\'s.csv', 'CSV')"
+        );
+        assert_eq!(
+            TableFunction::glob("logs", "*.json.gz").to_sql().unwrap(),
+            "file('logs/*.json.gz', 'JSONEachRow')"
+        );
+        assert_eq!(
+            TableFunction::file("data").to_sql().unwrap(),
+            "file('data')"
+        );
+        assert_eq!(
+            TableFunction::file("notes.txt")
+                .structure("line String")
+                .to_sql()
+                .unwrap(),
+            "file('notes.txt', 'auto', 'line String')"
+        );
+    }
+
+    #[test]
+    fn test_s3_with_credentials_and_structure() {
+        let sql = TableFunction::s3("https://bucket.s3.amazonaws.com/a.parquet?versionId=1")
+            .credentials("key", "secret")
+            .structure("id UInt64")
+            .to_sql()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "s3('https://bucket.s3.amazonaws.com/a.parquet?versionId=1', 'key', 'secret', \
+             'Parquet', 'id UInt64')"
+        );
+    }
+
+    #[test]
+    fn test_explicit_compression() {
+        assert_eq!(
+            TableFunction::url("https://example.com/export")
+                .format(InputFormat::CSV)
+                .compression(Compression::Gzip)
+                .to_sql()
+                .unwrap(),
+            "url('https://example.com/export', 'CSV', 'auto', 'gzip')"
+        );
+    }
+
+    #[test]
+    fn test_parse_describe() {
+        let columns = ColumnSchema::parse_describe("id\tInt64\t\t\t\t\t\nmsg\tNullable(String)\n");
+        assert_eq!(columns.len(), 2);
+        assert_eq!(columns[1].ty, "Nullable(String)");
+    }
+}
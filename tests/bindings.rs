@@ -0,0 +1,46 @@
+//! Checks that the checked-in `src/bindings.rs` fallback matches the bindings
+//! generated from the libchdb header at build time.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+/// Collect every `pub fn` declaration, with whitespace normalized.
+fn functions(bindings: &str) -> BTreeSet<String> {
+    bindings
+        .split("pub fn ")
+        .skip(1)
+        .filter_map(|decl| decl.split_once(';'))
+        .map(|(decl, _)| decl.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[test]
+fn test_checked_in_bindings_match_header() {
+    let generated = Path::new(env!("OUT_DIR")).join("bindings.rs");
+    let Ok(generated) = fs::read_to_string(&generated) else {
+        eprintln!("No generated bindings (docs.rs or `vendored-bindings` build), skipping");
+        return;
+    };
+    let checked_in =
+        fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("src/bindings.rs")).unwrap();
+
+    let generated = functions(&generated);
+    let checked_in = functions(&checked_in);
+    let missing: Vec<_> = generated.difference(&checked_in).collect();
+    let extra: Vec<_> = checked_in.difference(&generated).collect();
+    assert!(
+        missing.is_empty() && extra.is_empty(),
+        "src/bindings.rs is out of date with chdb.h; regenerate it from OUT_DIR/bindings.rs.\n\
+         Missing: {missing:#?}\nExtra: {extra:#?}"
+    );
+}
+
+#[test]
+fn test_functions_parser() {
+    let decls = functions("extern \"C\" {\n    pub fn chdb_query(\n        conn: chdb_connection,\n    ) -> *mut chdb_result;\n}\n");
+    assert_eq!(
+        decls.into_iter().collect::<Vec<_>>(),
+        ["chdb_query( conn: chdb_connection, ) -> *mut chdb_result"]
+    );
+}
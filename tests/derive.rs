@@ -0,0 +1,104 @@
+//! Tests for `#[derive(ChdbRow)]` and the typed row APIs.
+#![cfg(feature = "derive")]
+
+use chdb_rust::error::Result;
+use chdb_rust::row::{decode_rows, encode_rows, Row};
+use chdb_rust::session::SessionBuilder;
+use chdb_rust::ChdbRow;
+
+#[derive(Debug, Clone, PartialEq, ChdbRow)]
+struct Event {
+    #[chdb(order_by)]
+    id: u64,
+    #[chdb(type = "LowCardinality(String)")]
+    kind: String,
+    #[chdb(rename = "payload_json", codec = "ZSTD(3)")]
+    payload: String,
+    score: Option<f64>,
+    tags: Vec<String>,
+}
+
+fn sample() -> Vec<Event> {
+    vec![
+        Event {
+            id: 1,
+            kind: "click".into(),
+            payload: "{}".into(),
+            score: Some(0.5),
+            tags: vec!["a".into(), "b".into()],
+        },
+        Event {
+            id: 2,
+            kind: "view".into(),
+            payload: "it's \0 binary".into(),
+            score: None,
+            tags: vec![],
+        },
+    ]
+}
+
+#[test]
+fn test_derive_columns_and_ddl() -> Result<()> {
+    assert_eq!(
+        Event::COLUMNS,
+        &["id", "kind", "payload_json", "score", "tags"]
+    );
+    assert_eq!(
+        Event::create_table("events").to_sql()?,
+        "CREATE TABLE `events` (`id` UInt64, `kind` LowCardinality(String), \
+         `payload_json` String CODEC(ZSTD(3)), `score` Nullable(Float64), \
+         `tags` Array(String)) ENGINE = MergeTree() ORDER BY (`id`)"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_derive_round_trip() -> Result<()> {
+    let rows = sample();
+    let decoded: Vec<Event> = decode_rows(&encode_rows(&rows))?;
+    assert_eq!(decoded, rows);
+    Ok(())
+}
+
+#[test]
+fn test_insert_and_query_rows() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_auto_cleanup(true)
+        .build()?;
+
+    session.execute(&Event::create_table("events").to_sql()?, None)?;
+    session.insert_rows("events", &sample())?;
+
+    let rows: Vec<Event> = session.query_rows("SELECT * FROM events ORDER BY id")?;
+    assert_eq!(rows, sample());
+    Ok(())
+}
+
+#[test]
+fn test_insert_oversized_row() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_auto_cleanup(true)
+        .build()?;
+
+    session.execute(&Event::create_table("events").to_sql()?, None)?;
+    let mut rows = sample();
+    rows[1].payload = "\0".repeat(256 * 1024);
+    assert!(session.insert_rows("events", &rows).is_err());
+
+    // Rows that fit are still split across statements as needed.
+    let rows: Vec<Event> = (0..2000)
+        .map(|id| Event {
+            id,
+            payload: "x".repeat(100),
+            ..sample()[0].clone()
+        })
+        .collect();
+    session.insert_rows("events", &rows)?;
+    let stored: Vec<Event> = session.query_rows("SELECT * FROM events ORDER BY id")?;
+    assert_eq!(stored, rows);
+    Ok(())
+}
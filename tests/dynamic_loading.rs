@@ -0,0 +1,27 @@
+//! Tests for the `dynamic-loading` feature.
+#![cfg(feature = "dynamic-loading")]
+
+use chdb_rust::connection::Connection;
+use chdb_rust::error::Error;
+use chdb_rust::format::OutputFormat;
+
+#[test]
+fn test_missing_library() {
+    let err = chdb_rust::load_library("/nonexistent/libchdb.so").unwrap_err();
+    assert!(matches!(err, Error::LibraryLoad(_)), "{err:?}");
+    assert!(err.to_string().contains("/nonexistent/libchdb.so"));
+}
+
+#[test]
+fn test_query_through_loaded_library() {
+    // Resolved from CHDB_LIBRARY_PATH or the default search path.
+    match Connection::open_in_memory() {
+        Ok(conn) => {
+            let result = conn.query("SELECT 1", OutputFormat::CSV).unwrap();
+            assert_eq!(result.data_utf8_lossy(), "1\n");
+            assert!(chdb_rust::library_info().unwrap().path.is_some());
+        }
+        Err(Error::LibraryLoad(msg)) => eprintln!("libchdb not available, skipping: {msg}"),
+        Err(e) => panic!("unexpected error: {e:?}"),
+    }
+}
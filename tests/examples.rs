@@ -5,13 +5,21 @@
 //! to ensure reliable execution.
 
 use chdb_rust::arg::Arg;
-use chdb_rust::error::Result;
+use chdb_rust::backup::{BackupOptions, BackupStatus};
+use chdb_rust::error::{Error, Result};
 use chdb_rust::execute;
+use chdb_rust::explain::{ExplainKind, IndexUsage};
 use chdb_rust::format::InputFormat;
 use chdb_rust::format::OutputFormat;
+use chdb_rust::format::{Compression, EscapingRule, FormatOptions};
 use chdb_rust::log_level::LogLevel;
+use chdb_rust::pool::PoolBuilder;
 use chdb_rust::session::SessionBuilder;
+use chdb_rust::snapshot;
+use chdb_rust::table_function::TableFunction;
 use std::fs;
+use std::thread;
+use std::time::Duration;
 
 #[test]
 fn test_stateful() -> Result<()> {
@@ -122,6 +130,493 @@ fn test_output_formats() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_library_info() -> Result<()> {
+    let info = chdb_rust::library_info()?;
+    assert!(info.version.chars().next().unwrap().is_ascii_digit());
+    let path = info.path.expect("libchdb path");
+    assert!(path.to_string_lossy().contains("chdb"));
+
+    let version = chdb_rust::version()?;
+    assert!(version >= chdb_rust::library::MIN_SUPPORTED_VERSION);
+    assert!(info
+        .version
+        .starts_with(&format!("{}.{}.", version.major, version.minor)));
+    Ok(())
+}
+
+#[test]
+fn test_query_log() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_auto_cleanup(true)
+        .build()?;
+    let conn = session.connection();
+
+    let result = conn.query_with_id(
+        "SELECT sum(number) FROM numbers(1000)",
+        OutputFormat::CSV,
+        Some("test-sum"),
+    )?;
+    assert_eq!(result.query_id(), Some("test-sum"));
+    let generated = conn.query_with_id("SELECT 1", OutputFormat::CSV, None)?;
+    assert_eq!(generated.query_id().map(str::len), Some(36));
+
+    let entry = session.query_log("test-sum")?.expect("query_log entry");
+    assert_eq!(entry.query_id, "test-sum");
+    assert_eq!(entry.read_rows, 1000);
+    assert_eq!(entry.result_rows, 1);
+    assert!(!entry.is_error());
+
+    assert!(conn
+        .query_with_id("SELECT * FROM missing", OutputFormat::CSV, Some("test-err"))
+        .is_err());
+    let entry = session.query_log("test-err")?.expect("query_log entry");
+    assert_eq!(entry.exception_code.map(|c| c.0), Some(60));
+
+    // The tag survives a trailing comment and merges with a SETTINGS clause.
+    conn.query_with_id("SELECT 1 -- done", OutputFormat::CSV, Some("test-comment"))?;
+    assert!(session.query_log("test-comment")?.is_some());
+    let result = conn.query_with_id(
+        "SELECT 1 SETTINGS max_threads = 1",
+        OutputFormat::CSV,
+        Some("test-settings"),
+    )?;
+    assert_eq!(result.data_utf8_lossy(), "1\n");
+    assert!(session.query_log("test-settings")?.is_some());
+
+    assert!(session.query_log("never-run")?.is_none());
+    Ok(())
+}
+
+#[test]
+fn test_query_ids_concurrent() -> Result<()> {
+    let pool = PoolBuilder::new()
+        .with_max_in_flight(2)
+        .build(chdb_rust::connection::Connection::open_in_memory()?);
+
+    let handles: Vec<_> = ["thread-a", "thread-b"]
+        .into_iter()
+        .map(|id| {
+            let pool = pool.clone();
+            thread::spawn(move || -> Result<()> {
+                for i in 0..20 {
+                    let sql = format!("SELECT '{id}', {i}");
+                    let result = pool
+                        .get()?
+                        .query_with_id(&sql, OutputFormat::CSV, Some(id))?;
+                    assert_eq!(result.query_id(), Some(id));
+                }
+                Ok(())
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap()?;
+    }
+
+    let ctx = pool.get()?;
+    ctx.query("SYSTEM FLUSH LOGS", OutputFormat::CSV)?;
+    for id in ["thread-a", "thread-b"] {
+        let sql = format!(
+            "SELECT countIf(position(query, '{id}') > 0), count() FROM system.query_log \
+             WHERE log_comment = '{id}' AND type = 'QueryFinish'"
+        );
+        let result = ctx.query(&sql, OutputFormat::CSV)?;
+        assert_eq!(result.data_utf8_lossy().trim(), "20,20");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_query_with_progress() -> Result<()> {
+    let conn = chdb_rust::connection::Connection::open_in_memory()?;
+    let sql = "SELECT sum(number) AS s FROM numbers_mt(10000000)";
+
+    let mut updates = Vec::new();
+    let output = conn.query_with_progress(sql, OutputFormat::JSONEachRowWithProgress, |p| {
+        updates.push(p)
+    })?;
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("49999995000000"));
+    assert!(!output.contains("\"progress\""));
+    let last = updates.last().expect("progress updates");
+    assert!(last.read_rows > 0 && last.read_rows <= 10_000_000);
+    assert!(updates.windows(2).all(|w| w[0].read_rows <= w[1].read_rows));
+
+    let mut updates = 0;
+    let output = conn.query_with_progress(sql, OutputFormat::CSV, |_| updates += 1)?;
+    assert_eq!(output, b"49999995000000\n");
+    assert!(updates > 0);
+    Ok(())
+}
+
+#[test]
+fn test_pool() -> Result<()> {
+    let pool = PoolBuilder::new()
+        .with_max_in_flight(2)
+        .with_max_wait(Duration::from_millis(100))
+        .build(chdb_rust::connection::Connection::open_in_memory()?);
+
+    let handles: Vec<_> = (1..=8u64)
+        .map(|n| {
+            let pool = pool.clone();
+            thread::spawn(move || -> Result<String> {
+                let ctx = pool.get_timeout(Duration::from_secs(30))?;
+                let sql = format!("SELECT sum(number) FROM numbers({n})");
+                Ok(ctx
+                    .query(&sql, OutputFormat::CSV)?
+                    .data_utf8_lossy()
+                    .into_owned())
+            })
+        })
+        .collect();
+    for (n, handle) in (1..=8u64).zip(handles) {
+        assert_eq!(
+            handle.join().unwrap()?.trim(),
+            (n * (n - 1) / 2).to_string()
+        );
+    }
+    assert_eq!(pool.in_flight(), 0);
+
+    let first = pool.get()?;
+    let _second = pool.get()?;
+    assert!(pool.try_get().is_none());
+    assert!(matches!(pool.get(), Err(Error::PoolTimeout(_))));
+    drop(first);
+    assert!(pool.try_get().is_some());
+
+    // Settings travel with each query; shared session state is refused.
+    let ctx = pool.get()?;
+    assert!(matches!(
+        ctx.query("SET max_threads = 1", OutputFormat::CSV),
+        Err(Error::InvalidData(_))
+    ));
+    assert!(matches!(
+        ctx.query("USE system", OutputFormat::CSV),
+        Err(Error::InvalidData(_))
+    ));
+    let options = FormatOptions::new().setting("max_threads", 1);
+    let result = ctx.query_with_options(
+        "SELECT getSetting('max_threads')",
+        OutputFormat::CSV,
+        &options,
+    )?;
+    assert_eq!(result.data_utf8_lossy().trim(), "1");
+    Ok(())
+}
+
+#[test]
+fn test_read_only_session() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    {
+        let session = SessionBuilder::new().with_data_path(tmp.path()).build()?;
+        session.execute(
+            "CREATE TABLE events (id UInt64) ENGINE = MergeTree ORDER BY id",
+            None,
+        )?;
+        session.execute("INSERT INTO events VALUES (1), (2)", None)?;
+    }
+
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .read_only(true)
+        .build()?;
+    assert!(session.is_read_only());
+    let result = session.execute("SELECT count() FROM events", None)?;
+    assert_eq!(result.data_utf8_lossy().trim(), "2");
+
+    for sql in [
+        "INSERT INTO events VALUES (3)",
+        "DROP TABLE events",
+        "/* cleanup */ TRUNCATE TABLE events",
+        "SELECT 1; DROP TABLE events",
+    ] {
+        assert!(matches!(
+            session.execute(sql, None),
+            Err(Error::ReadOnly(_))
+        ));
+    }
+    // The server rejects writes that bypass the session checks.
+    assert!(session
+        .connection()
+        .query("INSERT INTO events VALUES (3)", OutputFormat::CSV)
+        .is_err());
+
+    // Looking up the query log does not need SYSTEM FLUSH LOGS.
+    session.query_log("no-such-query")?;
+    Ok(())
+}
+
+#[test]
+fn test_temporary_session() -> Result<()> {
+    let session = SessionBuilder::new().temporary().build()?;
+    let path = session.data_path().to_path_buf();
+    assert!(path.starts_with(std::env::temp_dir()));
+    session.execute(
+        "CREATE TABLE t (id UInt64) ENGINE = MergeTree ORDER BY id",
+        None,
+    )?;
+    session.execute("INSERT INTO t VALUES (1)", None)?;
+    assert!(path.exists());
+    drop(session);
+    assert!(!path.exists());
+
+    let path = std::panic::catch_unwind(|| {
+        let session = SessionBuilder::new().temporary().build().unwrap();
+        let path = session.data_path().to_path_buf();
+        std::panic::panic_any(path);
+    })
+    .unwrap_err()
+    .downcast::<std::path::PathBuf>()
+    .unwrap();
+    assert!(!path.exists());
+
+    // An explicit data path replaces the temporary directory and its cleanup.
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .temporary()
+        .with_data_path(tmp.path())
+        .build()?;
+    drop(session);
+    assert!(tmp.path().exists());
+    Ok(())
+}
+
+#[test]
+fn test_data_dir_lock() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new().with_data_path(tmp.path()).build()?;
+
+    let err = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_lock_timeout(Some(Duration::from_millis(100)))
+        .build()
+        .unwrap_err();
+    match err {
+        Error::DataDirLocked { pid } => assert_eq!(pid, Some(std::process::id())),
+        e => panic!("Expected DataDirLocked, got {e:?}"),
+    }
+
+    drop(session);
+    SessionBuilder::new().with_data_path(tmp.path()).build()?;
+    Ok(())
+}
+
+#[test]
+fn test_backup_and_restore() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .temporary()
+        .with_backup_dir(tmp.path())
+        .build()?;
+    session.execute(
+        "CREATE TABLE events (id UInt64) ENGINE = MergeTree ORDER BY id",
+        None,
+    )?;
+    session.execute("INSERT INTO events SELECT number FROM numbers(1000)", None)?;
+
+    // Backups outside the backup directory are refused.
+    let outside = tempdir::TempDir::new("chdb-rust")?;
+    assert!(session
+        .backup_to(outside.path().join("full"), None)
+        .is_err());
+
+    let options = BackupOptions::new().with_table("default.events");
+    let full = session.backup_to(tmp.path().join("full"), Some(&options))?;
+    assert_eq!(full.status, BackupStatus::BackupCreated);
+    assert!(full.num_files > 0);
+
+    session.execute(
+        "INSERT INTO events SELECT number FROM numbers(1000, 10)",
+        None,
+    )?;
+    let incremental = options.clone().with_base_backup(tmp.path().join("full"));
+    let info = session.backup_to(tmp.path().join("incremental"), Some(&incremental))?;
+    assert!(info.status.is_success());
+    assert!(info.num_entries < info.num_files);
+
+    session.execute("DROP TABLE events", None)?;
+    let info = session.restore_from(tmp.path().join("incremental"), Some(&options))?;
+    assert_eq!(info.status, BackupStatus::Restored);
+    let result = session.execute("SELECT count() FROM events", None)?;
+    assert_eq!(result.data_utf8_lossy().trim(), "1010");
+    Ok(())
+}
+
+#[test]
+fn test_snapshot() -> Result<()> {
+    let name = format!("chdb-rust-test-{}", std::process::id());
+    {
+        let session = SessionBuilder::new().temporary().build()?;
+        session.execute(
+            "CREATE TABLE users (id UInt64) ENGINE = MergeTree ORDER BY id",
+            None,
+        )?;
+        session.execute("INSERT INTO users SELECT number FROM numbers(100)", None)?;
+        session.snapshot(&name)?;
+    }
+    assert!(snapshot::exists(&name));
+
+    for _ in 0..2 {
+        let session = SessionBuilder::new().from_snapshot(&name).build()?;
+        let result = session.execute("SELECT count() FROM users", None)?;
+        assert_eq!(result.data_utf8_lossy().trim(), "100");
+        session.execute("TRUNCATE TABLE users", None)?;
+    }
+
+    snapshot::remove(&name)?;
+    assert!(matches!(
+        SessionBuilder::new().from_snapshot(&name).build(),
+        Err(Error::SnapshotNotFound(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_explain() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_auto_cleanup(true)
+        .build()?;
+    session.execute(
+        "CREATE TABLE events (id UInt64, kind String) ENGINE = MergeTree ORDER BY id \
+         SETTINGS index_granularity = 1024",
+        None,
+    )?;
+    session.execute(
+        "INSERT INTO events SELECT number, 'click' FROM numbers(100000)",
+        None,
+    )?;
+
+    let explain = session.explain("SELECT * FROM events WHERE id = 42", ExplainKind::Plan)?;
+    let plan = explain.plan().expect("plan");
+    assert!(plan.uses_primary_key());
+    let read = plan
+        .find("ReadFromMergeTree")
+        .expect("ReadFromMergeTree step");
+    let pk = read
+        .indexes
+        .iter()
+        .find(|i| i.kind == IndexUsage::PRIMARY_KEY)
+        .expect("primary key index");
+    assert_eq!(pk.keys, ["id"]);
+    assert!(pk.selected_granules < pk.initial_granules);
+
+    let explain = session.explain("SELECT * FROM events WHERE kind = 'x'", ExplainKind::Plan)?;
+    assert!(!explain.plan().unwrap().uses_primary_key());
+
+    let explain = session.explain("SELECT count() FROM events", ExplainKind::Estimate)?;
+    assert_eq!(explain.estimates().unwrap()[0].table, "events");
+
+    for kind in [
+        ExplainKind::Ast,
+        ExplainKind::Syntax,
+        ExplainKind::Pipeline,
+        ExplainKind::QueryTree,
+    ] {
+        let explain = session.explain("SELECT id FROM events WHERE id > 1", kind)?;
+        assert!(!explain.lines().unwrap().is_empty(), "{kind}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_formats_known_to_chdb() -> Result<()> {
+    let result = execute("SELECT name, is_input, is_output FROM system.formats", None)?;
+    let output = result.data_utf8_lossy();
+    let supports = |name: &str, column: usize| {
+        output.lines().any(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            fields[0] == name && fields[column] == "1"
+        })
+    };
+
+    for format in InputFormat::all() {
+        assert!(
+            supports(format.as_str(), 1),
+            "{format} is not an input format"
+        );
+    }
+    for format in OutputFormat::all() {
+        assert!(
+            supports(format.as_str(), 2),
+            "{format} is not an output format"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_format_options() -> Result<()> {
+    let options = FormatOptions::new()
+        .csv_delimiter(';')
+        .null_representation("NULL");
+    let result = execute(
+        "SELECT 1, NULL",
+        Some(&[
+            Arg::OutputFormat(OutputFormat::CSV),
+            Arg::FormatOptions(options),
+        ]),
+    )?;
+    assert_eq!(result.data_utf8_lossy(), "1;NULL\n");
+
+    let options = FormatOptions::new()
+        .custom_escaping_rule(EscapingRule::Quoted)
+        .custom_field_delimiter(" | ");
+    let result = execute(
+        "SELECT 1, 'a'",
+        Some(&[
+            Arg::OutputFormat(OutputFormat::CustomSeparated),
+            Arg::FormatOptions(options),
+        ]),
+    )?;
+    assert_eq!(result.data_utf8_lossy(), "1 | 'a'\n");
+
+    let options = FormatOptions::new().template_row_format("id=${id:Escaped}");
+    let result = execute(
+        "SELECT 7 AS id",
+        Some(&[
+            Arg::OutputFormat(OutputFormat::Template),
+            Arg::FormatOptions(options),
+        ]),
+    )?;
+    assert_eq!(result.data_utf8_lossy().trim_end(), "id=7");
+    Ok(())
+}
+
+#[test]
+fn test_session_format_options() -> Result<()> {
+    let session = SessionBuilder::new()
+        .temporary()
+        .with_arg(Arg::OutputFormat(OutputFormat::CSV))
+        .with_arg(Arg::FormatOptions(FormatOptions::new().csv_delimiter(';')))
+        .build()?;
+    // The defaults must not leak into DDL or INSERT statements.
+    session.execute(
+        "CREATE TABLE t (id UInt64, name String) ENGINE = MergeTree ORDER BY id",
+        None,
+    )?;
+    session.execute("INSERT INTO t VALUES (1, 'a')", None)?;
+    let result = session.execute("SELECT * FROM t SETTINGS max_threads = 1", None)?;
+    assert_eq!(result.data_utf8_lossy(), "1;\"a\"\n");
+
+    let options = FormatOptions::new().csv_delimiter('|');
+    let result = session.execute("SELECT * FROM t", Some(&[Arg::FormatOptions(options)]))?;
+    assert_eq!(result.data_utf8_lossy(), "1|\"a\"\n");
+    for sql in [
+        "SELECT * FROM t -- all",
+        "SELECT * FROM t SETTINGS max_threads = 1",
+    ] {
+        let options = FormatOptions::new().csv_delimiter('|');
+        let result = session.execute(sql, Some(&[Arg::FormatOptions(options)]))?;
+        assert_eq!(result.data_utf8_lossy(), "1|\"a\"\n");
+    }
+    Ok(())
+}
+
 #[test]
 fn test_query_result_statistics() -> Result<()> {
     let result = execute(
@@ -451,3 +946,94 @@ fn test_session_without_auto_cleanup() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_read_file_and_infer_schema() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_auto_cleanup(true)
+        .build()?;
+
+    let result = session
+        .read_file("tests/logs.csv", None, Some("id UInt64, msg String"))?
+        .filter("id = ?", [1])
+        .execute(
+            &session,
+            Some(&[Arg::OutputFormat(OutputFormat::JSONEachRow)]),
+        )?;
+    assert_eq!(result.data_utf8_lossy(), "{\"id\":1,\"msg\":\"test\"}\n");
+
+    let schema = session.infer_schema("tests/logs.csv")?;
+    assert_eq!(schema.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn test_query_to_writer_and_file() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path().join("db"))
+        .with_auto_cleanup(true)
+        .build()?;
+    let conn = session.connection();
+
+    let mut buf = Vec::new();
+    let written =
+        conn.query_to_writer("SELECT number FROM numbers(3)", OutputFormat::CSV, &mut buf)?;
+    assert_eq!(buf, b"0\n1\n2\n");
+    assert_eq!(written, 6);
+
+    let csv_path = tmp.path().join("numbers.csv");
+    conn.query_to_file("SELECT number FROM numbers(3)", &csv_path)?;
+    assert_eq!(fs::read_to_string(&csv_path)?, "0\n1\n2\n");
+
+    let gz_path = tmp.path().join("numbers.csv.gz");
+    conn.query_to_file("SELECT number FROM numbers(3)", &gz_path)?;
+    assert!(fs::metadata(&gz_path)?.len() > 0);
+
+    let zst_path = tmp.path().join("numbers.out");
+    conn.query_to_file_compressed(
+        "SELECT number FROM numbers(3)",
+        &zst_path,
+        OutputFormat::CSV,
+        Compression::Zstd,
+        Some(3),
+    )?;
+    let source = TableFunction::file(&zst_path)
+        .format(InputFormat::CSV)
+        .compression(Compression::Zstd);
+    let result = conn.query(
+        &format!("SELECT sum(c1) FROM {}", source.to_sql()?),
+        OutputFormat::CSV,
+    )?;
+    assert_eq!(result.data_utf8_lossy(), "3\n");
+
+    let gz = conn.query_compressed(
+        "SELECT number FROM numbers(3)",
+        OutputFormat::CSV,
+        Compression::Gzip,
+        None,
+    )?;
+    assert_eq!(&gz[..2], b"\x1f\x8b");
+
+    // A trailing comment and semicolon are fine; several statements are not.
+    conn.query_compressed(
+        "SELECT number FROM numbers(3); -- three rows",
+        OutputFormat::CSV,
+        Compression::Gzip,
+        None,
+    )?;
+    assert!(matches!(
+        conn.query_compressed(
+            "SELECT 1; SELECT 2",
+            OutputFormat::CSV,
+            Compression::Gzip,
+            None,
+        ),
+        Err(Error::InvalidData(_))
+    ));
+
+    Ok(())
+}
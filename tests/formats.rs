@@ -0,0 +1,30 @@
+//! Tests checking the format enums against the formats registered in libchdb.
+
+use chdb_rust::connection::Connection;
+use chdb_rust::error::Result;
+use chdb_rust::format::{InputFormat, OutputFormat};
+
+#[test]
+fn test_formats_match_library() -> Result<()> {
+    let conn = Connection::open_in_memory()?;
+    let result = conn.query(
+        "SELECT name, is_input, is_output FROM system.formats",
+        OutputFormat::TabSeparated,
+    )?;
+    let output = result.data_utf8_lossy();
+    let formats: Vec<Vec<&str>> = output.lines().map(|l| l.split('\t').collect()).collect();
+    let row = |name: &str| {
+        formats
+            .iter()
+            .find(|fields| fields[0] == name)
+            .unwrap_or_else(|| panic!("{name} is not registered in libchdb"))
+    };
+
+    for format in InputFormat::all() {
+        assert_eq!(row(format.as_str())[1], "1", "{format} input support");
+    }
+    for format in OutputFormat::all() {
+        assert_eq!(row(format.as_str())[2], "1", "{format} output support");
+    }
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Tests for the `log` feature.
+#![cfg(feature = "log")]
+
+use std::sync::{Mutex, OnceLock};
+
+use chdb_rust::log_level::LogLevel;
+use chdb_rust::session::SessionBuilder;
+
+static RECORDS: Mutex<Vec<(log::Level, String)>> = Mutex::new(Vec::new());
+
+struct Collector;
+
+impl log::Log for Collector {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() == "chdb"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let entry = (record.level(), record.args().to_string());
+            RECORDS.lock().unwrap().push(entry);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install [`Collector`] once per test binary. Returns `false` if another
+/// logger was already installed, in which case no records can be observed.
+fn install_collector() -> bool {
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+    *INSTALLED.get_or_init(|| {
+        let installed = log::set_logger(&Collector).is_ok();
+        log::set_max_level(log::LevelFilter::Trace);
+        installed
+    })
+}
+
+#[test]
+fn test_logs_are_forwarded() -> chdb_rust::error::Result<()> {
+    let collecting = install_collector();
+
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_log_capture(LogLevel::Debug)
+        .with_auto_cleanup(true)
+        .build()?;
+    session.execute("SELECT 1", None)?;
+    // Dropping the session drains the log file.
+    drop(session);
+
+    if !collecting {
+        return Ok(());
+    }
+    let records = RECORDS.lock().unwrap();
+    assert!(!records.is_empty());
+    assert!(records.iter().all(|(level, _)| *level <= log::Level::Debug));
+    assert!(records.iter().any(|(_, msg)| msg.contains("SELECT 1")));
+    Ok(())
+}
@@ -0,0 +1,58 @@
+//! Tests for the schema migrations runner.
+
+use chdb_rust::error::{Error, Result};
+use chdb_rust::migrations::{Migration, Migrator};
+use chdb_rust::session::SessionBuilder;
+
+fn migrations() -> Vec<Migration> {
+    vec![
+        Migration::new(
+            1,
+            "create_events",
+            "CREATE TABLE events (id UInt64) ENGINE = MergeTree() ORDER BY id",
+        ),
+        Migration::new(2, "add_kind", "ALTER TABLE events ADD COLUMN kind String"),
+        Migration::new(
+            3,
+            "create_users",
+            "CREATE TABLE users (id UInt64) ENGINE = MergeTree() ORDER BY id;\n\
+             INSERT INTO users VALUES (1), (2);",
+        ),
+    ]
+}
+
+#[test]
+fn test_migrations_apply_once_and_verify() -> Result<()> {
+    let tmp = tempdir::TempDir::new("chdb-rust")?;
+    let session = SessionBuilder::new()
+        .with_data_path(tmp.path())
+        .with_auto_cleanup(true)
+        .build()?;
+
+    // Dry run reports everything as pending without touching the database.
+    let report = Migrator::new(migrations())?.dry_run(true).run(&session)?;
+    assert_eq!(report.applied, vec![1, 2, 3]);
+    assert!(Migrator::new(migrations())?.applied(&session)?.is_empty());
+
+    let report = Migrator::new(migrations())?.run(&session)?;
+    assert_eq!(report.applied, vec![1, 2, 3]);
+    let result = session.execute("SELECT count() FROM users", None)?;
+    assert_eq!(result.data_utf8_lossy().trim(), "2");
+
+    // A second run is a no-op.
+    let report = Migrator::new(migrations())?.run(&session)?;
+    assert!(report.applied.is_empty());
+    assert_eq!(report.already_applied, vec![1, 2, 3]);
+
+    session.execute("INSERT INTO events VALUES (1, 'click')", None)?;
+
+    // Editing an applied migration is rejected.
+    let mut edited = migrations();
+    edited[0] = Migration::new(1, "create_events", "SELECT 1");
+    match Migrator::new(edited)?.run(&session) {
+        Err(Error::MigrationChecksumMismatch { version: 1, .. }) => {}
+        other => panic!("Expected checksum mismatch, got {other:?}"),
+    }
+
+    Ok(())
+}
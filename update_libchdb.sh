@@ -6,18 +6,43 @@ set -e
 command -v curl >/dev/null 2>&1 || { echo >&2 "curl is required but it's not installed. Aborting."; exit 1; }
 command -v tar >/dev/null 2>&1 || { echo >&2 "tar is required but it's not installed. Aborting."; exit 1; }
 
+# Print the SHA-256 digest of a file
+sha256_of() {
+    if command -v sha256sum >/dev/null 2>&1; then
+        sha256sum "$1" | awk '{print $1}'
+    else
+        shasum -a 256 "$1" | awk '{print $1}'
+    fi
+}
+
 # Function to download and extract the file
+#
+# The archive is checked against the expected SHA-256 digest, if one is given,
+# before it is unpacked. A mismatch aborts the script.
 download_and_extract() {
     local url=$1
+    local expected_sha256=$2
     local file="libchdb.tar.gz"
 
     echo "Attempting to download $PLATFORM from $url"
 
     # Download the file with a retry logic
-    if curl -L -o "$file" "$url"; then
+    if curl -fL -o "$file" "$url"; then
         echo "Download successful."
 
-        # Optional: Verify download integrity here, if checksums are provided
+        if [[ -n "$expected_sha256" ]]; then
+            local actual_sha256
+            actual_sha256=$(sha256_of "$file")
+            if [[ "$actual_sha256" != "$(echo "$expected_sha256" | tr 'A-F' 'a-f')" ]]; then
+                echo >&2 "SHA-256 mismatch for $PLATFORM: expected $expected_sha256, got $actual_sha256."
+                echo >&2 "The download may be corrupted or tampered with; set CHDB_LIB_SHA256 for custom builds. Aborting."
+                rm -f "$file"
+                exit 1
+            fi
+            echo "Checksum verified."
+        else
+            echo "Warning: no pinned SHA-256 digest for $PLATFORM $LATEST_RELEASE; set CHDB_LIB_SHA256 to verify the download."
+        fi
 
         # Untar the file
         if tar -xzf "$file"; then
@@ -53,17 +78,21 @@ case "$(uname -s)" in
         ;;
 esac
 
-# Main download URL
+# SHA-256 digests of the $LATEST_RELEASE assets, keep in sync with LIBCHDB_SHA256
+# in build.rs. CHDB_LIB_SHA256 overrides the pinned digest for custom builds.
+# TODO: fill in the digests of the v26.1.0 assets.
+pinned_sha256() {
+    case "$1" in
+        *) echo "" ;;
+    esac
+}
+EXPECTED_SHA256=${CHDB_LIB_SHA256:-$(pinned_sha256 "$PLATFORM")}
+
 DOWNLOAD_URL="https://github.com/chdb-io/chdb-core/releases/download/$LATEST_RELEASE/$PLATFORM"
-FALLBACK_URL="https://github.com/chdb-io/chdb-core/releases/latest/download/$PLATFORM"
 
-# Try the main download URL first
-if ! download_and_extract "$DOWNLOAD_URL"; then
-    echo "Retrying with fallback URL..."
-    if ! download_and_extract "$FALLBACK_URL"; then
-        echo "Both primary and fallback downloads failed. Aborting."
-        exit 1
-    fi
+if ! download_and_extract "$DOWNLOAD_URL" "$EXPECTED_SHA256"; then
+    echo "Download failed. Aborting."
+    exit 1
 fi
 
 chmod +x libchdb.so